@@ -1,187 +1,798 @@
+use std::io;
+use std::io::fs;
 use std::io::fs::PathExtensions;
-use std::io::{File, Reader, BufferedReader};
+use std::io::{File, Reader, IoError, IoResult};
 use std::cell::RefCell;
+use std::cmp;
 
+use input::Input;
+use rope;
+use rope::Rope;
+use syntax;
+use syntax::{Highlighter, Span};
 
 pub struct Buffer {
-    pub file_path: String,
-    pub lines: Vec<RefCell<Line>>,
+    /// The file the buffer is saved to, or None if it hasn't been given one
+    pub file_path: Option<Path>,
+    pub lines: Rope<Line>,
+
+    /// The line ending written after each line when the buffer is saved
+    pub line_ending: LineEnding,
+    /// Whether the last line is followed by a line ending
+    pub trailing_newline: bool,
+    /// Whether the file loaded used more than one kind of line ending
+    pub mixed_line_endings: bool,
+
+    log: OperationLog,
+    // the highlighting of the lines, if the buffer is in a known language
+    highlighter: RefCell<Option<Highlighter>>,
 }
 
 impl Buffer {
     /// Create a new buffer instance
     pub fn new() -> Buffer {
         Buffer {
-            file_path: String::new(),
-            lines: Vec::new(),
+            file_path: None,
+            lines: Rope::new(),
+            line_ending: LineEnding::Lf,
+            trailing_newline: true,
+            mixed_line_endings: false,
+            log: OperationLog::new(),
+            highlighter: RefCell::new(None),
         }
     }
 
     /// Create a new buffer with a single line
     pub fn new_empty() -> Buffer {
         let mut buffer = Buffer::new();
-        buffer.lines.push(RefCell::new(Line::new(Vec::new(), 0)));
-        buffer.file_path = String::from_str("untitled");
-
+        buffer.lines.push(Line::new(String::new()));
         buffer
     }
 
-    fn lines_from_reader<R: Reader>(reader: &mut BufferedReader<R>) -> Vec<RefCell<Line>> {
-        let mut v = vec![];
-        // for every line in the reader we add a corresponding line to the buffer
-        for (index, line) in reader.lines().enumerate() {
-            let mut data = line.unwrap().into_bytes();
-            let last_index = data.len() - 1;
-            if data[last_index] == '\n' as u8 {
-                data.pop();
-            }
-            v.push(RefCell::new(Line::new(data, index)));
+    /// Replace the lines of the buffer with the contents of `reader`.
+    ///
+    /// The first line ending found is used for the whole buffer, so a file
+    /// which mixes them is saved with just that one, and is marked as mixed
+    /// so that the change can be pointed out. Text which isn't valid UTF-8 is
+    /// refused, as it couldn't be saved back unchanged.
+    fn load<R: Reader>(&mut self, reader: &mut R) -> IoResult<()> {
+        let data = try!(reader.read_to_end());
+        let text = match String::from_utf8(data) {
+            Ok(text) => text,
+            Err(_)   => return Err(IoError {
+                kind: io::InvalidInput,
+                desc: "the file is not valid UTF-8",
+                detail: None,
+            }),
+        };
+        let (lines, line_ending, mixed_line_endings, trailing_newline) = split_lines(text.as_slice());
+
+        self.lines = Rope::from_vec(lines);
+        self.line_ending = line_ending.unwrap_or(LineEnding::Lf);
+        self.trailing_newline = trailing_newline;
+        self.mixed_line_endings = mixed_line_endings;
+        if let Some(ref mut highlighter) = *self.highlighter.borrow_mut() {
+            highlighter.clear();
         }
-        v
+        Ok(())
     }
 
-    pub fn new_from_reader<R: Reader>(reader: R) -> Buffer {
-        let mut r = BufferedReader::new(reader);
-        Buffer {
-            lines: Buffer::lines_from_reader(&mut r),
-            file_path: String::from_str("untitled"),
-        }
+    pub fn new_from_reader<R: Reader>(mut reader: R) -> IoResult<Buffer> {
+        let mut buffer = Buffer::new();
+        try!(buffer.load(&mut reader));
+        Ok(buffer)
     }
 
-    /// Create a new buffer instance and load the given file
-    pub fn new_from_file(path: &Path) -> Buffer {
+    /// Create a new buffer instance and load the given file, or an empty one
+    /// which is saved to the file if it doesn't exist yet
+    pub fn new_from_file(path: &Path) -> IoResult<Buffer> {
         let mut buffer = Buffer::new();
 
         if path.exists() {
-            let mut file = BufferedReader::new(File::open(path));
-
-            buffer.lines = Buffer::lines_from_reader(&mut file);
+            try!(buffer.load(&mut try!(File::open(path))));
         } else {
-            buffer.lines.push(RefCell::new(Line::new(Vec::new(), 0)));
+            buffer.lines.push(Line::new(String::new()));
         }
 
-        buffer.file_path = path.as_str().unwrap().to_string();
-        buffer
+        buffer.set_file_path(path.clone());
+        Ok(buffer)
+    }
+
+    /// Create a new buffer instance from the given input source
+    pub fn new_from_input(source: Input) -> IoResult<Buffer> {
+        match source {
+            Input::Filename(path) => {
+                match path {
+                    Some(s) => Buffer::new_from_file(&Path::new(s)),
+                    None    => Ok(Buffer::new_empty()),
+                }
+            },
+            Input::Stdin(reader) => {
+                Buffer::new_from_reader(reader)
+            },
+        }
+    }
+
+    /// Get the name to show for the buffer, which is its file path if it
+    /// has one
+    pub fn get_name(&self) -> String {
+        match self.file_path {
+            Some(ref path) => path.display().to_string(),
+            None           => String::from_str("untitled"),
+        }
+    }
+
+    /// Set the file the buffer is saved to, and highlight it in the language
+    /// the file's extension says it is in
+    pub fn set_file_path(&mut self, path: Path) {
+        let highlighter = syntax::grammar_for_path(&path).map(Highlighter::new);
+        self.highlighter = RefCell::new(highlighter);
+        self.file_path = Some(path);
+    }
+
+    /// Check whether the buffer has changed since it was loaded or last
+    /// saved
+    pub fn is_modified(&self) -> bool {
+        self.log.is_modified()
+    }
+
+    /// Set the line ending written when the buffer is saved
+    pub fn set_line_ending(&mut self, line_ending: LineEnding) {
+        self.line_ending = line_ending;
+        self.log.mark_unsaved();
+    }
+
+    /// Get a warning to show when the buffer is opened if its file mixed
+    /// line endings, since saving it will change all but the first kind
+    pub fn get_line_ending_warning(&self) -> Option<String> {
+        if self.mixed_line_endings {
+            Some(format!("{} mixes line endings, which will all be saved as {}",
+                         self.get_name(), self.line_ending.name()))
+        } else {
+            None
+        }
     }
 
+    /// Get the text for the status line, in which a buffer with unsaved
+    /// changes is marked with a '+'
     pub fn get_status_text(&self) -> String {
-        let file_path = self.file_path.clone();
-        let line_count = self.lines.len();
-        format!("{}, lines: {}", file_path, line_count)
+        let file_path = self.get_name();
+        let modified = if self.is_modified() { " [+]" } else { "" };
+        let line_count = self.line_count();
+        format!("{}{}, lines: {}", file_path, modified, line_count)
+    }
+
+    /// Write the buffer to its file.
+    ///
+    /// The lines are written to a temporary file alongside it, which is then
+    /// renamed over the original. Either the whole buffer is saved or the
+    /// file is left as it was.
+    pub fn save(&mut self) -> IoResult<()> {
+        let path = match self.file_path {
+            Some(ref path) => path,
+            None => return Err(IoError {
+                kind: io::InvalidInput,
+                desc: "the buffer has no file path",
+                detail: None,
+            }),
+        };
+
+        let file_name = format!(".{}.iota-save", path.filename_str().unwrap_or("untitled"));
+        let temp_path = path.with_filename(file_name);
+
+        let result = self.write_to(&temp_path, path).and_then(|_| fs::rename(&temp_path, path));
+        if result.is_err() && temp_path.exists() {
+            let _ = fs::unlink(&temp_path);
+        }
+        if result.is_ok() {
+            self.log.mark_saved();
+        }
+        result
     }
 
-    fn fix_linenums(&mut self) {
+    /// Write every line of the buffer to a new file at `path`, with the same
+    /// permissions as the file at `original` if there is one
+    fn write_to(&self, path: &Path, original: &Path) -> IoResult<()> {
+        let mut file = try!(File::create(path));
+        let line_ending = self.line_ending.as_str();
+        let line_count = self.line_count();
+
         for (index, line) in self.lines.iter().enumerate() {
-            line.borrow_mut().linenum = index;
+            try!(file.write_str(line.data.as_slice()));
+            if index + 1 < line_count || self.trailing_newline {
+                try!(file.write_str(line_ending));
+            }
+        }
+        try!(file.fsync());
+
+        if let Ok(stat) = original.stat() {
+            try!(fs::chmod(path, stat.perm));
         }
+        Ok(())
     }
 
-    pub fn insert_line(&mut self, offset: uint, mut line_num: uint) {
-        // split the current line at the cursor position
-        let (_, new_data) = self.split_line(offset, line_num);
-        {
-            // truncate the current line
-            let line = self.get_line_at(line_num);
-            line.unwrap().borrow_mut().data.truncate(offset);
+    /// Get the number of lines in the buffer
+    pub fn line_count(&self) -> uint {
+        self.lines.len()
+    }
+
+    /// Get the line identified by `line_num`.
+    ///
+    /// Panics if there is no such line.
+    pub fn get_line(&self, line_num: uint) -> &Line {
+        match self.lines.get(line_num) {
+            Some(line) => line,
+            None       => panic!("no line {} in buffer of {} lines", line_num, self.line_count()),
+        }
+    }
+
+    /// Get a mutable reference to the line identified by `line_num`.
+    ///
+    /// Panics if there is no such line.
+    pub fn get_line_mut(&mut self, line_num: uint) -> &mut Line {
+        let line_count = self.line_count();
+        if let Some(ref mut highlighter) = *self.highlighter.borrow_mut() {
+            highlighter.line_changed(line_num);
+        }
+        match self.lines.get_mut(line_num) {
+            Some(line) => line,
+            None       => panic!("no line {} in buffer of {} lines", line_num, line_count),
+        }
+    }
+
+    /// Get the spans to draw line `line_num` in, which are empty if the
+    /// buffer isn't in a known language
+    pub fn get_highlighting(&self, line_num: uint) -> Vec<Span> {
+        match *self.highlighter.borrow_mut() {
+            Some(ref mut highlighter) => highlighter.get_spans(&self.lines, line_num),
+            None                      => Vec::new(),
+        }
+    }
+
+    /// Insert `line` at `line_num`, keeping the highlighting of the lines
+    /// around it
+    fn insert_line_at(&mut self, line_num: uint, line: Line) {
+        self.lines.insert(line_num, line);
+        if let Some(ref mut highlighter) = *self.highlighter.borrow_mut() {
+            highlighter.line_inserted(line_num);
+        }
+    }
+
+    /// Remove the line at `line_num`, keeping the highlighting of the lines
+    /// around it
+    fn remove_line_at(&mut self, line_num: uint) -> Line {
+        if let Some(ref mut highlighter) = *self.highlighter.borrow_mut() {
+            highlighter.line_removed(line_num);
         }
+        self.lines.remove(line_num).unwrap()
+    }
 
-        line_num += 1;
+    /// Iterate over the lines of the buffer, starting at `line_num`
+    pub fn lines_from(&self, line_num: uint) -> rope::Iter<Line> {
+        self.lines.iter_from(line_num)
+    }
 
-        self.lines.insert(line_num, RefCell::new(Line::new(new_data, line_num)));
+    pub fn insert_line(&mut self, offset: uint, line_num: uint) {
+        // split the current line at the cursor position
+        let (_, new_data) = self.split_line(offset, line_num);
+        self.get_line_mut(line_num).data.truncate(offset);
 
-        self.fix_linenums();
+        self.insert_line_at(line_num + 1, Line::new(new_data));
     }
 
     /// Join the line identified by `line_num` with the one at `line_num - 1 `.
     pub fn join_line_with_previous(&mut self, offset: uint, line_num: uint) -> uint {
         // if the line_num is 0 (ie the first line), don't do anything
-        if line_num == 0 { return offset }
+        if line_num == 0 || line_num >= self.line_count() { return offset }
 
-        let mut current_line_data: Vec<u8>;
-        {
-            // get current line data
-            let current_line = self.get_line_at(line_num).unwrap();
-            current_line_data = current_line.borrow().data.clone();
-        }
+        let current_line = self.remove_line_at(line_num);
 
         // update the previous line
-        let new_cursor_offset = match self.get_line_at(line_num -1) {
-            None => offset,
-            Some(line) => {
-                let line_len = line.borrow().data.len();
-                line.borrow_mut().data.push_all(current_line_data.as_slice());
-                line_len
+        let previous_line = self.get_line_mut(line_num - 1);
+        let new_cursor_offset = previous_line.len();
+        previous_line.data.push_str(current_line.data.as_slice());
+
+        return new_cursor_offset
+    }
+
+    /// Split the line identified by `line_num` at `offset`
+    fn split_line(&self, offset: uint, line_num: uint) -> (String, String) {
+        let data = self.get_line(line_num).data.as_slice();
+        let old = String::from_str(data.slice_to(offset));
+        let new = String::from_str(data.slice_from(offset));
+
+        return (old, new)
+    }
+
+    /// Insert `text` at `offset` on the line identified by `line_num`.
+    ///
+    /// Every '\n' in `text` splits the line in the same way `insert_line` does.
+    /// Returns the (offset, line_num) position just after the inserted text.
+    pub fn insert_text(&mut self, mut offset: uint, mut line_num: uint, text: &str) -> (uint, uint) {
+        for (index, segment) in text.split('\n').enumerate() {
+            if index > 0 {
+                self.insert_line(offset, line_num);
+                offset = 0;
+                line_num += 1;
             }
+
+            let line = self.get_line_mut(line_num);
+            let tail = String::from_str(line.data.as_slice().slice_from(offset));
+            line.data.truncate(offset);
+            line.data.push_str(segment);
+            line.data.push_str(tail.as_slice());
+            offset += segment.len();
+        }
+
+        (offset, line_num)
+    }
+
+    /// Get the text between the (offset, line_num) positions `start` and `end`,
+    /// with a '\n' for every line break.
+    pub fn get_text(&self, start: (uint, uint), end: (uint, uint)) -> String {
+        let (start_offset, start_line) = start;
+        let (end_offset, end_line) = end;
+        let mut text = String::new();
+
+        for line_num in range(start_line, end_line + 1) {
+            let data = self.get_line(line_num).data.as_slice();
+            let from = if line_num == start_line { start_offset } else { 0 };
+            let to = if line_num == end_line { end_offset } else { data.len() };
+
+            if line_num > start_line { text.push('\n'); }
+            text.push_str(data.slice(from, to));
+        }
+        text
+    }
+
+    /// Remove the text between the (offset, line_num) positions `start` and `end`.
+    ///
+    /// Lines spanned by the range are joined together. Returns the removed text,
+    /// with a '\n' for every line break that was removed.
+    pub fn remove_text(&mut self, start: (uint, uint), end: (uint, uint)) -> String {
+        let (start_offset, start_line) = start;
+        let (end_offset, end_line) = end;
+        let mut removed = String::new();
+
+        // everything after the end of the range is kept on the first line
+        let tail = String::from_str(self.get_line(end_line).data.as_slice().slice_from(end_offset));
+
+        if start_line == end_line {
+            removed.push_str(self.get_line(start_line).data.as_slice().slice(start_offset, end_offset));
+        } else {
+            removed.push_str(self.get_line(start_line).data.as_slice().slice_from(start_offset));
+            for line_num in range(start_line + 1, end_line + 1) {
+                let line = self.remove_line_at(start_line + 1);
+                removed.push('\n');
+                if line_num == end_line {
+                    removed.push_str(line.data.as_slice().slice_to(end_offset));
+                } else {
+                    removed.push_str(line.data.as_slice());
+                }
+            }
+        }
+
+        let first_line = self.get_line_mut(start_line);
+        first_line.data.truncate(start_offset);
+        first_line.data.push_str(tail.as_slice());
+
+        removed
+    }
+
+    /// Replace the text between `start` and `end` with `text`.
+    ///
+    /// Returns the text which was replaced, and the position just past the
+    /// text put in its place.
+    pub fn replace_text(&mut self, start: (uint, uint), end: (uint, uint), text: &str) -> (String, (uint, uint)) {
+        let removed = self.remove_text(start, end);
+        let (offset, line_num) = start;
+        let text_end = self.insert_text(offset, line_num, text);
+        (removed, text_end)
+    }
+
+    /// Record an operation which has been applied to the buffer, so that it
+    /// can be undone later.
+    pub fn record(&mut self, op: Operation) {
+        self.log.record(op);
+    }
+
+    /// Revert the most recent operation.
+    ///
+    /// Returns the (offset, line_num) position the cursor had before the
+    /// operation was made, or None if there is nothing to undo.
+    pub fn undo(&mut self) -> Option<(uint, uint)> {
+        let op = match self.log.undo() {
+            Some(op) => op,
+            None     => return None,
         };
 
-        self.lines.remove(line_num);
-        self.fix_linenums();
+        let (offset, line_num) = op.position;
+        match op.change {
+            Change::Insert(ref text) => {
+                let end = end_of_text(op.position, text.as_slice());
+                self.remove_text(op.position, end);
+            }
+            Change::Delete(ref text) => {
+                self.insert_text(offset, line_num, text.as_slice());
+            }
+            Change::Replace(ref old, ref new) => {
+                let end = end_of_text(op.position, new.as_slice());
+                self.remove_text(op.position, end);
+                self.insert_text(offset, line_num, old.as_slice());
+            }
+        }
 
-        return new_cursor_offset
+        Some(op.cursor_before)
     }
 
-    // TODO(greg): refactor this to use Vec::partition
-    /// Split the line identified by `line_num` at `offset`
-    fn split_line(&mut self, offset: uint, line_num: uint) -> (Vec<u8>, Vec<u8>) {
-        let line = self.get_line_at(line_num).unwrap();
+    /// Re-apply the most recently undone operation.
+    ///
+    /// Returns the (offset, line_num) position the cursor had after the
+    /// operation was made, or None if there is nothing to redo.
+    pub fn redo(&mut self) -> Option<(uint, uint)> {
+        let op = match self.log.redo() {
+            Some(op) => op,
+            None     => return None,
+        };
+
+        let (offset, line_num) = op.position;
+        match op.change {
+            Change::Insert(ref text) => {
+                self.insert_text(offset, line_num, text.as_slice());
+            }
+            Change::Delete(ref text) => {
+                let end = end_of_text(op.position, text.as_slice());
+                self.remove_text(op.position, end);
+            }
+            Change::Replace(ref old, ref new) => {
+                let end = end_of_text(op.position, old.as_slice());
+                self.remove_text(op.position, end);
+                self.insert_text(offset, line_num, new.as_slice());
+            }
+        }
+
+        Some(op.cursor_after)
+    }
+}
 
-        let data = line.borrow().data.clone();
-        let old_data = data.slice_to(offset);
-        let new_data = data.slice_from(offset);
 
-        let mut new = Vec::new(); new.push_all(new_data);
-        let mut old = Vec::new(); old.push_all(old_data);
+/// The sequence of characters which ends each line of a file
+#[deriving(Clone, PartialEq, Show)]
+pub enum LineEnding {
+    /// "\n", used on Unix
+    Lf,
+    /// "\r\n", used on Windows
+    CrLf,
+    /// "\r", used on classic Mac OS
+    Cr,
+}
 
-        return (old, new)
+impl LineEnding {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            LineEnding::Lf   => "\n",
+            LineEnding::CrLf => "\r\n",
+            LineEnding::Cr   => "\r",
+        }
     }
 
-    fn get_line_at(&self, line_num: uint) -> Option<&RefCell<Line>> {
-        let num_lines = self.lines.len() -1;
-        if line_num > num_lines { return None }
-        Some(&self.lines[line_num])
+    /// Get the name to show for the line ending
+    pub fn name(&self) -> &'static str {
+        match *self {
+            LineEnding::Lf   => "LF",
+            LineEnding::CrLf => "CRLF",
+            LineEnding::Cr   => "CR",
+        }
+    }
+}
+
+
+/// Split `text` into lines, at any of the line endings.
+///
+/// A lone "\r" only ends a line in text without any "\n", so a stray one in
+/// an LF or CRLF file is kept as part of its line. Returns the lines, the
+/// first line ending found, whether a different one was found after it and
+/// whether the text ends with a line ending. There is always at least one
+/// line.
+fn split_lines(text: &str) -> (Vec<Line>, Option<LineEnding>, bool, bool) {
+    let bytes = text.as_bytes();
+    let cr_ends_lines = !text.contains_char('\n');
+    let mut lines = Vec::new();
+    let mut line_ending = None;
+    let mut mixed = false;
+    let mut start = 0;
+    let mut index = 0;
+
+    while index < bytes.len() {
+        let found = match bytes[index] {
+            b'\n' => Some((LineEnding::Lf, 1)),
+            b'\r' if index + 1 < bytes.len() && bytes[index + 1] == b'\n' => Some((LineEnding::CrLf, 2)),
+            b'\r' if cr_ends_lines => Some((LineEnding::Cr, 1)),
+            _     => None,
+        };
+
+        match found {
+            Some((ending, len)) => {
+                lines.push(Line::new(String::from_str(text.slice(start, index))));
+                if line_ending.is_none() {
+                    line_ending = Some(ending);
+                } else if line_ending != Some(ending) {
+                    mixed = true;
+                }
+                index += len;
+                start = index;
+            }
+            None => index += 1,
+        }
     }
 
+    let trailing_newline = !lines.is_empty() && start == text.len();
+    if !trailing_newline {
+        lines.push(Line::new(String::from_str(text.slice_from(start))));
+    }
+
+    (lines, line_ending, mixed, trailing_newline)
+}
+
+
+/// Get the position just after `text`, if it were inserted at `start`.
+fn end_of_text(start: (uint, uint), text: &str) -> (uint, uint) {
+    let (offset, line_num) = start;
+    let breaks = text.chars().filter(|&c| c == '\n').count();
+
+    match text.rfind('\n') {
+        Some(index) => (text.len() - index - 1, line_num + breaks),
+        None        => (offset + text.len(), line_num),
+    }
+}
+
+
+/// A single change made to the contents of a Buffer.
+#[deriving(Clone, PartialEq, Show)]
+pub enum Change {
+    /// Text was inserted. A '\n' in the text split a line in two.
+    Insert(String),
+    /// Text was removed. A '\n' in the text joined two lines together.
+    Delete(String),
+    /// The first text was removed, and the second inserted in its place.
+    Replace(String, String),
 }
 
 
+/// A Change along with where it happened and where it left the cursor.
+///
+/// All positions are (offset, line_num) pairs.
+#[deriving(Clone, PartialEq, Show)]
+pub struct Operation {
+    pub change: Change,
+    pub position: (uint, uint),
+    pub cursor_before: (uint, uint),
+    pub cursor_after: (uint, uint),
+}
+
+
+/// The history of Operations made to a Buffer.
+///
+/// Undoing an Operation moves it onto the redo stack. Recording a new
+/// Operation throws away anything which could have been redone.
+pub struct OperationLog {
+    undo_stack: Vec<Operation>,
+    redo_stack: Vec<Operation>,
+    // the number of operations on the undo stack when the buffer was last
+    // saved, or None if undoing and redoing can no longer get back to how
+    // the buffer was then
+    saved_at: Option<uint>,
+}
+
+impl OperationLog {
+    pub fn new() -> OperationLog {
+        OperationLog {
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            saved_at: Some(0),
+        }
+    }
+
+    pub fn record(&mut self, op: Operation) {
+        // the saved state is lost along with the operations being redone
+        if let Some(saved_at) = self.saved_at {
+            if saved_at > self.undo_stack.len() {
+                self.saved_at = None;
+            }
+        }
+        self.undo_stack.push(op);
+        self.redo_stack.clear();
+    }
+
+    /// Note that the buffer has just been saved
+    pub fn mark_saved(&mut self) {
+        self.saved_at = Some(self.undo_stack.len());
+    }
+
+    /// Note a change to the buffer which can't be undone, so that it counts
+    /// as modified until it is saved again
+    pub fn mark_unsaved(&mut self) {
+        self.saved_at = None;
+    }
+
+    /// Check whether operations have been made or undone since the buffer
+    /// was last saved
+    pub fn is_modified(&self) -> bool {
+        self.saved_at != Some(self.undo_stack.len())
+    }
+
+    pub fn undo(&mut self) -> Option<Operation> {
+        let op = self.undo_stack.pop();
+        if let Some(ref op) = op {
+            self.redo_stack.push(op.clone());
+        }
+        op
+    }
+
+    pub fn redo(&mut self) -> Option<Operation> {
+        let op = self.redo_stack.pop();
+        if let Some(ref op) = op {
+            self.undo_stack.push(op.clone());
+        }
+        op
+    }
+}
+
+
+/// Get the number of screen columns taken up by a grapheme cluster which is
+/// drawn starting at `column`.
+///
+/// A tab reaches to the next multiple of `tab_width`. Wide East Asian
+/// characters take two columns, everything else takes one.
+pub fn display_width(grapheme: &str, column: uint, tab_width: uint) -> uint {
+    if grapheme == "\t" {
+        let tab_width = cmp::max(tab_width, 1);
+        return tab_width - column % tab_width
+    }
+    cmp::max(grapheme.width(false), 1)
+}
+
+
+/// A single line of text in a Buffer.
+///
+/// The text is stored as UTF-8. Offsets into a line are byte offsets, which
+/// should always fall on a grapheme cluster boundary.
+///
+/// Lines don't know their own line number, it is given by their position in
+/// the Buffer, so inserting or removing a line doesn't touch any of the others.
 pub struct Line {
-    pub data: Vec<u8>,
-    pub linenum: uint,
+    pub data: String,
 }
 
 impl Line {
     /// Create a new line instance
-    pub fn new(data: Vec<u8>, line_num: uint) -> Line {
+    pub fn new(data: String) -> Line {
         Line{
             data: data,
-            linenum: line_num,
         }
     }
 
-    /// Get the length of the current line
+    /// Get the length of the current line, in bytes
     pub fn len(&self) -> uint {
         self.data.len()
     }
+
+    /// Get the offset just after the grapheme cluster which starts at `offset`
+    pub fn next_grapheme_boundary(&self, offset: uint) -> uint {
+        match self.data.as_slice().slice_from(offset).graphemes(true).next() {
+            Some(grapheme) => offset + grapheme.len(),
+            None           => offset,
+        }
+    }
+
+    /// Get the offset of the grapheme cluster which ends at `offset`
+    pub fn prev_grapheme_boundary(&self, offset: uint) -> uint {
+        match self.data.as_slice().slice_to(offset).graphemes(true).last() {
+            Some(grapheme) => offset - grapheme.len(),
+            None           => offset,
+        }
+    }
+
+    /// Get the start of the grapheme cluster containing `offset`
+    pub fn grapheme_boundary_at(&self, offset: uint) -> uint {
+        if offset >= self.len() { return self.len() }
+
+        let mut boundary = 0;
+        for (index, _) in self.data.as_slice().grapheme_indices(true) {
+            if index > offset { break }
+            boundary = index;
+        }
+        boundary
+    }
+
+    /// Get the number of screen columns taken up by the line up to `offset`,
+    /// with tabs expanded to every `tab_width` columns
+    pub fn width_to(&self, offset: uint, tab_width: uint) -> uint {
+        self.width_between(0, offset, tab_width)
+    }
+
+    /// Get the number of screen columns taken up by the text between `start`
+    /// and `end`, when drawn starting from the first column
+    pub fn width_between(&self, start: uint, end: uint, tab_width: uint) -> uint {
+        self.data.as_slice().slice(start, end).graphemes(true).fold(0, |column, grapheme| {
+            column + display_width(grapheme, column, tab_width)
+        })
+    }
+
+    /// Get the offset of the grapheme drawn at `column`, when the text between
+    /// `start` and `end` is drawn starting from the first column.
+    ///
+    /// Returns `end` if the text is narrower than `column`.
+    pub fn offset_at_column(&self, start: uint, end: uint, column: uint, tab_width: uint) -> uint {
+        let mut width = 0;
+        for (index, grapheme) in self.data.as_slice().slice(start, end).grapheme_indices(true) {
+            width += display_width(grapheme, width, tab_width);
+            if width > column {
+                return start + index
+            }
+        }
+        end
+    }
+
+    /// Get the offsets at which the line should be broken so that no row is
+    /// wider than `width` columns. The first row always starts at offset 0.
+    ///
+    /// Lines are broken after whitespace where possible, a word which is too
+    /// long for a row of its own is broken wherever it reaches the edge.
+    pub fn wrap_points(&self, width: uint, tab_width: uint) -> Vec<uint> {
+        let mut points = vec![0u];
+        let mut column = 0;
+        // the offset just after the last whitespace on the current row
+        let mut last_break: Option<uint> = None;
+
+        for (offset, grapheme) in self.data.as_slice().grapheme_indices(true) {
+            let mut grapheme_width = display_width(grapheme, column, tab_width);
+
+            if column > 0 && column + grapheme_width > width {
+                let row_start = last_break.unwrap_or(offset);
+                points.push(row_start);
+                column = self.width_between(row_start, offset, tab_width);
+                last_break = None;
+
+                // the rest of the word may still be too long for the new row
+                if column > 0 && column + display_width(grapheme, column, tab_width) > width {
+                    points.push(offset);
+                    column = 0;
+                }
+                grapheme_width = display_width(grapheme, column, tab_width);
+            }
+
+            column += grapheme_width;
+            if grapheme.chars().all(|c| c.is_whitespace()) {
+                last_break = Some(offset + grapheme.len());
+            }
+        }
+
+        points
+    }
 }
 
 
 #[cfg(test)]
 mod tests {
 
-    use std::cell::RefCell;
-    use buffer::Buffer;
-    use buffer::Line;
+    use std::io::{File, MemReader, TempDir};
+    use std::io::fs::PathExtensions;
+
+    use buffer::{Buffer, Change, Line, LineEnding, Operation};
+    use rope::Rope;
+    use syntax::{Span, Style};
     use utils::data_from_str;
 
     fn setup_buffer() -> Buffer {
         let mut buffer = Buffer::new();
-        buffer.file_path = String::from_str("/some/file.txt");
-        buffer.lines = vec!(
-            RefCell::new(Line::new(data_from_str("test"), 0)),
-            RefCell::new(Line::new(Vec::new(), 1)),
-            RefCell::new(Line::new(data_from_str("text file"), 2)),
-            RefCell::new(Line::new(data_from_str("content"), 3)),
-        );
+        buffer.file_path = Some(Path::new("/some/file.txt"));
+        buffer.lines = Rope::from_vec(vec!(
+            Line::new(data_from_str("test")),
+            Line::new(String::new()),
+            Line::new(data_from_str("text file")),
+            Line::new(data_from_str("content")),
+        ));
         buffer
     }
 
@@ -191,6 +802,161 @@ mod tests {
         assert_eq!(buffer.get_status_text(), "/some/file.txt, lines: 4".to_string())
     }
 
+    #[test]
+    fn buffers_without_a_path_are_untitled() {
+        let mut buffer = Buffer::new_empty();
+        assert_eq!(buffer.get_status_text(), "untitled, lines: 1".to_string());
+        assert!(buffer.save().is_err());
+    }
+
+    #[test]
+    fn save_replaces_the_whole_file() {
+        let dir = TempDir::new("iota").unwrap();
+        let path = dir.path().join("file.txt");
+        File::create(&path).write_str("a line which is longer than the buffer\n").unwrap();
+
+        let mut buffer = setup_buffer();
+        buffer.file_path = Some(path.clone());
+        buffer.save().unwrap();
+
+        let saved = File::open(&path).read_to_string().unwrap();
+        assert_eq!(saved, String::from_str("test\n\ntext file\ncontent\n"));
+        assert!(!dir.path().join(".file.txt.iota-save").exists());
+    }
+
+    #[test]
+    fn changes_are_tracked_until_saved() {
+        let dir = TempDir::new("iota").unwrap();
+        let mut buffer = setup_buffer();
+        buffer.file_path = Some(dir.path().join("file.txt"));
+        assert!(!buffer.is_modified());
+
+        buffer.insert_text(0, 0, "a");
+        buffer.record(Operation {
+            change: Change::Insert(String::from_str("a")),
+            position: (0, 0),
+            cursor_before: (0, 0),
+            cursor_after: (1, 0),
+        });
+        assert!(buffer.is_modified());
+        assert!(buffer.get_status_text().as_slice().contains("[+]"));
+
+        buffer.undo();
+        assert!(!buffer.is_modified());
+        buffer.redo();
+        buffer.save().unwrap();
+        assert!(!buffer.is_modified());
+        buffer.undo();
+        assert!(buffer.is_modified());
+
+        buffer.redo();
+        buffer.set_line_ending(LineEnding::CrLf);
+        assert!(buffer.is_modified());
+    }
+
+    #[test]
+    fn failed_save_leaves_no_temporary_file() {
+        let dir = TempDir::new("iota").unwrap();
+        let path = dir.path().join("missing").join("file.txt");
+
+        let mut buffer = setup_buffer();
+        buffer.file_path = Some(path.clone());
+
+        assert!(buffer.save().is_err());
+        assert!(!path.exists());
+    }
+
+    fn load_buffer(text: &str) -> Buffer {
+        Buffer::new_from_reader(MemReader::new(text.as_bytes().to_vec())).unwrap()
+    }
+
+    #[test]
+    fn loading_invalid_utf8_is_an_error() {
+        let data = vec![b'o', b'k', b'\n', 0xff, b'\n'];
+        assert!(Buffer::new_from_reader(MemReader::new(data)).is_err());
+    }
+
+    #[test]
+    fn opening_a_directory_is_an_error() {
+        let dir = TempDir::new("iota").unwrap();
+        assert!(Buffer::new_from_file(dir.path()).is_err());
+
+        let buffer = Buffer::new_from_file(&dir.path().join("new.txt")).unwrap();
+        assert_eq!(buffer_lines(&buffer), vec![""]);
+    }
+
+    fn buffer_lines(buffer: &Buffer) -> Vec<&str> {
+        buffer.lines.iter().map(|line| line.data.as_slice()).collect()
+    }
+
+    #[test]
+    fn loading_detects_crlf_line_endings() {
+        let buffer = load_buffer("first\r\n\r\nlast\r\n");
+
+        assert_eq!(buffer_lines(&buffer), vec!["first", "", "last"]);
+        assert_eq!(buffer.line_ending, LineEnding::CrLf);
+        assert!(buffer.trailing_newline);
+    }
+
+    #[test]
+    fn loading_detects_cr_line_endings_and_missing_final_newline() {
+        let buffer = load_buffer("first\rlast");
+
+        assert_eq!(buffer_lines(&buffer), vec!["first", "last"]);
+        assert_eq!(buffer.line_ending, LineEnding::Cr);
+        assert!(!buffer.trailing_newline);
+    }
+
+    #[test]
+    fn loading_keeps_a_lone_cr_in_a_file_with_lf_line_endings() {
+        let buffer = load_buffer("a\rb\nc\n");
+
+        assert_eq!(buffer_lines(&buffer), vec!["a\rb", "c"]);
+        assert_eq!(buffer.line_ending, LineEnding::Lf);
+        assert!(!buffer.mixed_line_endings);
+    }
+
+    #[test]
+    fn loading_marks_mixed_line_endings() {
+        let buffer = load_buffer("a\r\nb\nc\r\n");
+
+        assert_eq!(buffer_lines(&buffer), vec!["a", "b", "c"]);
+        assert_eq!(buffer.line_ending, LineEnding::CrLf);
+        assert!(buffer.mixed_line_endings);
+        assert!(buffer.get_line_ending_warning().is_some());
+
+        let buffer = load_buffer("a\r\nb\r\n");
+        assert!(!buffer.mixed_line_endings);
+        assert!(buffer.get_line_ending_warning().is_none());
+    }
+
+    #[test]
+    fn loading_empty_lines_and_empty_input() {
+        let buffer = load_buffer("\n\n");
+        assert_eq!(buffer_lines(&buffer), vec!["", ""]);
+        assert!(buffer.trailing_newline);
+
+        let buffer = load_buffer("");
+        assert_eq!(buffer_lines(&buffer), vec![""]);
+        assert_eq!(buffer.line_ending, LineEnding::Lf);
+        assert!(!buffer.trailing_newline);
+    }
+
+    #[test]
+    fn save_writes_back_the_original_line_endings() {
+        let dir = TempDir::new("iota").unwrap();
+        let path = dir.path().join("file.txt");
+
+        for text in ["a\r\nb\r\n", "a\rb", "a\nb\n", "a\rb\n", "", "\n"].iter() {
+            let mut buffer = load_buffer(*text);
+            buffer.file_path = Some(path.clone());
+            buffer.save().unwrap();
+
+            let saved = File::open(&path).read_to_string().unwrap();
+            assert_eq!(saved.as_slice(), *text);
+        }
+    }
+
     #[test]
     fn test_insert_line() {
         let mut buffer = setup_buffer();
@@ -204,20 +970,17 @@ mod tests {
         buffer.insert_line(1, 0);
         assert_eq!(buffer.lines.len(), 5);
 
-        let ref line = buffer.lines[1];
-        assert_eq!(line.borrow().data, data_from_str("est"));
+        assert_eq!(buffer.get_line(1).data, data_from_str("est"));
     }
 
     #[test]
-    fn test_line_numbers_are_fixed_after_adding_new_line() {
+    fn lines_stay_in_order_after_adding_new_line() {
         let mut buffer = setup_buffer();
         buffer.insert_line(1, 2);
         assert_eq!(buffer.lines.len(), 5);
 
-        // check that all linenums are sequential
-        for (index, line) in buffer.lines.iter().enumerate() {
-            assert_eq!(index, line.borrow().linenum);
-        }
+        let data: Vec<&str> = buffer.lines.iter().map(|line| line.data.as_slice()).collect();
+        assert_eq!(data, vec!["test", "", "t", "ext file", "content"]);
     }
 
     #[test]
@@ -227,7 +990,7 @@ mod tests {
         let offset = buffer.join_line_with_previous(0, 3);
 
         assert_eq!(buffer.lines.len(), 3);
-        assert_eq!(buffer.lines[2].borrow().data, data_from_str("text filecontent"));
+        assert_eq!(buffer.get_line(2).data, data_from_str("text filecontent"));
         assert_eq!(offset, 9);
     }
 
@@ -237,7 +1000,7 @@ mod tests {
         buffer.join_line_with_previous(0, 0);
 
         assert_eq!(buffer.lines.len(), 4);
-        assert_eq!(buffer.lines[0].borrow().data, data_from_str("test"));
+        assert_eq!(buffer.get_line(0).data, data_from_str("test"));
     }
 
     #[test]
@@ -249,5 +1012,168 @@ mod tests {
         assert_eq!(new, data_from_str("tent"));
     }
 
+    #[test]
+    fn grapheme_boundaries_skip_multibyte_chars() {
+        let line = Line::new(data_from_str("aé漢b"));
+
+        assert_eq!(line.next_grapheme_boundary(1), 3);
+        assert_eq!(line.next_grapheme_boundary(3), 6);
+        assert_eq!(line.prev_grapheme_boundary(6), 3);
+        assert_eq!(line.grapheme_boundary_at(4), 3);
+        assert_eq!(line.next_grapheme_boundary(7), 7);
+    }
+
+    #[test]
+    fn combining_marks_are_part_of_the_grapheme() {
+        let line = Line::new(data_from_str("e\u0301x"));
+
+        assert_eq!(line.next_grapheme_boundary(0), 3);
+        assert_eq!(line.prev_grapheme_boundary(3), 0);
+    }
+
+    #[test]
+    fn wide_chars_take_two_columns() {
+        let line = Line::new(data_from_str("a漢字b"));
+
+        assert_eq!(line.width_to(1, 4), 1);
+        assert_eq!(line.width_to(4, 4), 3);
+        assert_eq!(line.width_to(line.len(), 4), 6);
+    }
+
+    #[test]
+    fn tabs_reach_to_the_next_tab_stop() {
+        let line = Line::new(data_from_str("\tab\tc"));
+
+        assert_eq!(line.width_to(1, 4), 4);
+        assert_eq!(line.width_to(4, 4), 8);
+        assert_eq!(line.width_to(4, 8), 16);
+        assert_eq!(line.width_to(3, 3), 5);
+    }
+
+    #[test]
+    fn wrap_points_break_after_whitespace() {
+        let line = Line::new(data_from_str("hello world foo"));
+
+        assert_eq!(line.wrap_points(8, 4), vec![0, 6, 12]);
+        assert_eq!(line.wrap_points(80, 4), vec![0]);
+    }
+
+    #[test]
+    fn wrap_points_break_long_words_at_the_edge() {
+        let line = Line::new(data_from_str("abcdefghij"));
+        assert_eq!(line.wrap_points(4, 4), vec![0, 4, 8]);
+
+        let line = Line::new(data_from_str("a bcdefghij"));
+        assert_eq!(line.wrap_points(4, 4), vec![0, 2, 6, 10]);
+    }
+
+    #[test]
+    fn offset_at_column_finds_the_grapheme_under_the_column() {
+        let line = Line::new(data_from_str("a漢b"));
+
+        assert_eq!(line.offset_at_column(0, 5, 0, 4), 0);
+        assert_eq!(line.offset_at_column(0, 5, 2, 4), 1);
+        assert_eq!(line.offset_at_column(0, 5, 3, 4), 4);
+        assert_eq!(line.offset_at_column(0, 5, 9, 4), 5);
+    }
+
+    #[test]
+    fn insert_text_splits_lines_on_newlines() {
+        let mut buffer = setup_buffer();
+        let end = buffer.insert_text(2, 0, "ab\ncd");
+
+        assert_eq!(end, (2, 1));
+        assert_eq!(buffer.lines.len(), 5);
+        assert_eq!(buffer.get_line(0).data, data_from_str("teab"));
+        assert_eq!(buffer.get_line(1).data, data_from_str("cdst"));
+    }
+
+    #[test]
+    fn remove_text_joins_lines_in_range() {
+        let mut buffer = setup_buffer();
+        let removed = buffer.remove_text((2, 0), (4, 2));
+
+        assert_eq!(removed, String::from_str("st\n\ntext"));
+        assert_eq!(buffer.lines.len(), 2);
+        assert_eq!(buffer.get_line(0).data, data_from_str("te file"));
+        assert_eq!(buffer.get_line(1).data, data_from_str("content"));
+    }
+
+    #[test]
+    fn undo_reverts_recorded_operation() {
+        let mut buffer = setup_buffer();
+        buffer.insert_text(0, 3, "x");
+        buffer.record(Operation {
+            change: Change::Insert(String::from_str("x")),
+            position: (0, 3),
+            cursor_before: (0, 3),
+            cursor_after: (1, 3),
+        });
+
+        assert_eq!(buffer.undo(), Some((0, 3)));
+        assert_eq!(buffer.get_line(3).data, data_from_str("content"));
+        assert_eq!(buffer.undo(), None);
+    }
+
+    #[test]
+    fn redo_reapplies_undone_operation() {
+        let mut buffer = setup_buffer();
+        let removed = buffer.remove_text((4, 0), (0, 1));
+        buffer.record(Operation {
+            change: Change::Delete(removed),
+            position: (4, 0),
+            cursor_before: (0, 1),
+            cursor_after: (4, 0),
+        });
+
+        buffer.undo();
+        assert_eq!(buffer.lines.len(), 4);
+        assert_eq!(buffer.redo(), Some((4, 0)));
+        assert_eq!(buffer.lines.len(), 3);
+        assert_eq!(buffer.redo(), None);
+    }
+
+    #[test]
+    fn get_text_joins_lines_with_line_breaks() {
+        let buffer = setup_buffer();
+
+        assert_eq!(buffer.get_text((1, 0), (3, 0)), String::from_str("es"));
+        assert_eq!(buffer.get_text((2, 0), (4, 2)), String::from_str("st\n\ntext"));
+        assert_eq!(buffer.get_text((4, 0), (0, 1)), String::from_str("\n"));
+    }
+
+    #[test]
+    fn replaced_text_can_be_undone_and_redone() {
+        let mut buffer = setup_buffer();
+        let (removed, end) = buffer.replace_text((0, 2), (4, 2), "some");
+        assert_eq!(removed, String::from_str("text"));
+        assert_eq!(end, (4, 2));
+        buffer.record(Operation {
+            change: Change::Replace(removed, String::from_str("some")),
+            position: (0, 2),
+            cursor_before: (0, 2),
+            cursor_after: end,
+        });
+        assert_eq!(buffer.get_line(2).data, data_from_str("some file"));
+
+        assert_eq!(buffer.undo(), Some((0, 2)));
+        assert_eq!(buffer.get_line(2).data, data_from_str("text file"));
+        assert_eq!(buffer.redo(), Some((4, 2)));
+        assert_eq!(buffer.get_line(2).data, data_from_str("some file"));
+    }
+
+    #[test]
+    fn highlighting_follows_edits() {
+        let mut buffer = setup_buffer();
+        assert!(buffer.get_highlighting(0).is_empty());
+
+        buffer.set_file_path(Path::new("/some/file.rs"));
+        buffer.insert_text(0, 1, "/*");
+        assert_eq!(buffer.get_highlighting(3), vec![Span { start: 0, end: 7, style: Style::Comment }]);
+
+        buffer.remove_text((0, 1), (2, 1));
+        assert!(buffer.get_highlighting(3).is_empty());
+    }
+
 }
 
@@ -0,0 +1,279 @@
+//! Text which has been cut or copied, ready to be pasted.
+
+use std::io;
+use std::io::process::{Command, Process, ProcessExit, StdioContainer};
+use std::io::stdio;
+
+use serialize::base64::{ToBase64, STANDARD};
+
+// how many entries the KillRing keeps
+const KILL_RING_SIZE: uint = 60;
+
+// how long a copy or paste command is given to finish, in milliseconds, so
+// that one which hangs doesn't freeze the editor
+const COMMAND_TIMEOUT: u64 = 2000;
+
+
+/// A piece of text which has been cut or copied
+#[deriving(Clone, PartialEq, Show)]
+pub struct Kill {
+    pub text: String,
+    /// Whether the text is whole lines, ending with a '\n', which are pasted
+    /// above the cursor line rather than at the cursor
+    pub linewise: bool,
+}
+
+/// The text cut or copied most recently, along with the text cut or copied
+/// before it, which can be pasted in its place.
+pub struct KillRing {
+    // oldest first
+    entries: Vec<Kill>,
+}
+
+impl KillRing {
+    pub fn new() -> KillRing {
+        KillRing { entries: Vec::new() }
+    }
+
+    /// Add `text` as the newest entry, dropping the oldest once the ring is
+    /// full
+    pub fn push(&mut self, text: String, linewise: bool) {
+        if text.is_empty() { return }
+
+        self.entries.push(Kill { text: text, linewise: linewise });
+        if self.entries.len() > KILL_RING_SIZE {
+            self.entries.remove(0);
+        }
+    }
+
+    pub fn len(&self) -> uint {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Get the entry `index` places back from the newest one, which is at 0
+    pub fn get(&self, index: uint) -> Option<&Kill> {
+        if index >= self.entries.len() { return None }
+        Some(&self.entries[self.entries.len() - 1 - index])
+    }
+}
+
+
+/// Shares text cut or copied in iota with the clipboard of the rest of the
+/// desktop, and brings text from it back in to be pasted. It is set up once
+/// for the whole editor.
+///
+/// Text is copied with the OSC 52 escape sequence if that is turned on, which
+/// asks the terminal to put it on the clipboard and works over ssh too, and
+/// piped into a helper program such as `xclip` or `wl-copy` if one is
+/// configured, so either or both can be used. Terminals can't be relied on to
+/// give the clipboard back, so pasting from it needs a helper program such as
+/// `wl-paste`.
+pub struct SystemClipboard {
+    /// Whether text is put on the terminal's clipboard with OSC 52
+    pub osc52: bool,
+    /// A shell command to pipe text cut or copied into, such as `wl-copy`
+    pub copy_command: Option<String>,
+    /// A shell command whose output is pasted, such as `wl-paste -n`
+    pub paste_command: Option<String>,
+}
+
+impl SystemClipboard {
+    /// Create a clipboard which isn't shared with the desktop at all
+    pub fn new() -> SystemClipboard {
+        SystemClipboard {
+            osc52: false,
+            copy_command: None,
+            paste_command: None,
+        }
+    }
+
+    /// Put `text` on the clipboard in each of the ways turned on
+    pub fn copy(&self, text: &str) -> Result<(), String> {
+        let mut result = Ok(());
+        if self.osc52 {
+            let sequence = osc52_sequence(text);
+            if let Err(error) = stdio::stdout_raw().write_str(sequence.as_slice()) {
+                result = Err(error.to_string());
+            }
+        }
+        if let Some(ref command) = self.copy_command {
+            if let Err(error) = pipe_to_command(command.as_slice(), text) {
+                result = Err(error);
+            }
+        }
+        result
+    }
+
+    /// Get the text on the clipboard, or None if there is no way to read it
+    pub fn paste(&self) -> Option<Result<String, String>> {
+        self.paste_command.as_ref().map(|command| read_command_output(command.as_slice()))
+    }
+}
+
+/// Check whether text pasted from the clipboard is the same as text copied
+/// to it, allowing for a final line break being dropped or added on the way,
+/// as `wl-paste -n` does
+pub fn same_text(copied: &str, pasted: &str) -> bool {
+    fn without_line_break(text: &str) -> &str {
+        if text.ends_with("\n") { text.slice_to(text.len() - 1) } else { text }
+    }
+    without_line_break(copied) == without_line_break(pasted)
+}
+
+/// Wrap `text` in the OSC 52 escape sequence, which asks the terminal to put
+/// it on the clipboard
+pub fn osc52_sequence(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", text.as_bytes().to_base64(STANDARD))
+}
+
+/// Run `command` with the shell, writing `text` to its input
+fn pipe_to_command(command: &str, text: &str) -> Result<(), String> {
+    // helpers such as xclip stay in the background holding the clipboard,
+    // so their output isn't waited for
+    let mut process = match Command::new("sh").arg("-c").arg(command)
+                                  .stdout(StdioContainer::Ignored).spawn() {
+        Ok(process) => process,
+        Err(error)  => return Err(format!("{}: {}", command, error)),
+    };
+
+    // the text is written from another task, so that a command which
+    // doesn't read it can't block the editor past the timeout
+    let stdin = process.stdin.take();
+    let text = String::from_str(text);
+    let (send, recv) = channel();
+    spawn(proc() {
+        // the input is closed once stdin is dropped, so the command can finish
+        let written = match stdin {
+            Some(mut stdin) => stdin.write_str(text.as_slice()),
+            None            => Ok(()),
+        };
+        send.send(written);
+    });
+
+    let status = try!(wait_for(&mut process, command));
+    if let Err(error) = recv.recv() {
+        return Err(format!("{}: {}", command, error))
+    }
+    if status.success() { Ok(()) } else { Err(format!("{} failed: {}", command, status)) }
+}
+
+/// Run `command` with the shell and get what it writes out, with Windows
+/// style line endings turned into plain '\n's
+fn read_command_output(command: &str) -> Result<String, String> {
+    let mut process = match Command::new("sh").arg("-c").arg(command).spawn() {
+        Ok(process) => process,
+        Err(error)  => return Err(format!("{}: {}", command, error)),
+    };
+    drop(process.stdin.take());
+
+    // the output is read from another task, so that a command which writes
+    // a lot can't fill the pipe and block before it exits
+    let stdout = process.stdout.take();
+    let (send, recv) = channel();
+    spawn(proc() {
+        let output = match stdout {
+            Some(mut stdout) => stdout.read_to_end(),
+            None             => Ok(Vec::new()),
+        };
+        send.send(output);
+    });
+
+    let status = try!(wait_for(&mut process, command));
+    if !status.success() {
+        return Err(format!("{} failed: {}", command, status))
+    }
+    let output = match recv.recv() {
+        Ok(output) => output,
+        Err(error) => return Err(format!("{}: {}", command, error)),
+    };
+
+    let text = String::from_utf8_lossy(output.as_slice()).into_owned();
+    Ok(text.replace("\r\n", "\n"))
+}
+
+/// Wait for the process running `command` to finish, killing it if it takes
+/// longer than COMMAND_TIMEOUT
+fn wait_for(process: &mut Process, command: &str) -> Result<ProcessExit, String> {
+    process.set_timeout(Some(COMMAND_TIMEOUT));
+    match process.wait() {
+        Ok(status) => Ok(status),
+        Err(ref error) if error.kind == io::TimedOut => {
+            let _ = process.signal_kill();
+            process.set_timeout(None);
+            let _ = process.wait();
+            Err(format!("{} timed out", command))
+        }
+        Err(error) => Err(format!("{}: {}", command, error)),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use clipboard::{KILL_RING_SIZE, Kill, KillRing, SystemClipboard, osc52_sequence, same_text};
+
+    #[test]
+    fn newest_entry_comes_first() {
+        let mut ring = KillRing::new();
+        ring.push(String::from_str("one"), false);
+        ring.push(String::new(), false);
+        ring.push(String::from_str("two\n"), true);
+
+        assert_eq!(ring.len(), 2);
+        assert_eq!(ring.get(0), Some(&Kill { text: String::from_str("two\n"), linewise: true }));
+        assert_eq!(ring.get(1), Some(&Kill { text: String::from_str("one"), linewise: false }));
+        assert_eq!(ring.get(2), None);
+    }
+
+    #[test]
+    fn oldest_entries_are_dropped() {
+        let mut ring = KillRing::new();
+        for n in range(0, KILL_RING_SIZE + 5) {
+            ring.push(n.to_string(), false);
+        }
+
+        assert_eq!(ring.len(), KILL_RING_SIZE);
+        assert_eq!(ring.get(KILL_RING_SIZE - 1).unwrap().text, String::from_str("5"));
+    }
+
+    #[test]
+    fn osc52_sends_the_text_in_base64() {
+        assert_eq!(osc52_sequence("iota\n"), String::from_str("\x1b]52;c;aW90YQo=\x07"));
+    }
+
+    #[test]
+    fn text_is_pasted_from_the_output_of_a_command() {
+        let mut clipboard = SystemClipboard::new();
+        assert!(clipboard.paste().is_none());
+
+        clipboard.paste_command = Some(String::from_str("printf 'one\\r\\ntwo'"));
+        assert_eq!(clipboard.paste(), Some(Ok(String::from_str("one\ntwo"))));
+
+        clipboard.copy_command = Some(String::from_str("exit 1"));
+        assert!(clipboard.copy("text").is_err());
+    }
+
+    #[test]
+    fn commands_which_hang_are_given_up_on() {
+        let mut clipboard = SystemClipboard::new();
+        clipboard.paste_command = Some(String::from_str("sleep 10"));
+        assert_eq!(clipboard.paste(), Some(Err(String::from_str("sleep 10 timed out"))));
+
+        clipboard.copy_command = Some(String::from_str("sleep 10"));
+        assert_eq!(clipboard.copy("text"), Err(String::from_str("sleep 10 timed out")));
+    }
+
+    #[test]
+    fn a_final_line_break_makes_no_difference_to_pasted_text() {
+        assert!(same_text("one\n", "one"));
+        assert!(same_text("one", "one\n"));
+        assert!(same_text("one\n", "one\n"));
+        assert!(!same_text("one\n\n", "one"));
+        assert!(!same_text("one", "two"));
+    }
+}
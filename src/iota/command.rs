@@ -0,0 +1,186 @@
+use cursor::{Direction, Motion};
+use layout::Orientation;
+use modes::Mode;
+use search::SearchDirection;
+
+/// Something the Editor can be asked to do in response to a key press
+#[deriving(Clone, PartialEq, Show)]
+pub enum Command {
+    MoveCursor(Direction),
+    /// Move the cursor by a word or paragraph, or to the edge of the line or
+    /// buffer
+    Move(Motion),
+    /// Scroll the View up or down by a screen
+    ScrollPage(Direction),
+    /// Scroll the View up or down by a line, leaving the cursor where it is
+    /// unless it would go out of view
+    ScrollLine(Direction),
+    /// Ask for a number of lines and scroll the View by them, up if the
+    /// number is negative
+    ScrollLines,
+    /// Scroll the View so the cursor line is in the middle of it
+    Recenter,
+    /// Ask for a line, or a line and column, and move the cursor there
+    GoToLine,
+
+    InsertChar(char),
+    /// Insert spaces up to the next tab stop
+    InsertTab,
+    /// Split the line at the cursor
+    InsertLine,
+    DeleteChar(Direction),
+    /// Delete the selected text, and return to normal mode if selecting
+    DeleteSelection,
+    /// Delete the line the cursor is on
+    DeleteLine,
+    /// Indent the selected lines, or the cursor line, by a tab stop
+    Indent,
+    /// Take a tab stop of indentation off the selected lines, or the cursor
+    /// line
+    Dedent,
+    /// Start a selection at the cursor, or clear it if there is one
+    SetMark,
+
+    /// Cut the selection, or the cursor line, into the kill ring
+    Cut,
+    /// Copy the selection, or the cursor line, into the kill ring
+    Copy,
+    /// Paste the newest text in the kill ring
+    Paste,
+    /// Swap the text just pasted for the entry before it in the kill ring
+    PastePrevious,
+
+    /// Move past the char under the cursor and start inserting
+    Append,
+    /// Start a new line below the cursor and start inserting on it
+    OpenLineBelow,
+
+    Undo,
+    Redo,
+
+    Save,
+    /// Quit, asking first if any buffer has unsaved changes
+    Quit,
+    /// Quit without asking, throwing away any unsaved changes
+    ForceQuit,
+    Resize,
+
+    OpenBuffer,
+    /// Ask for the path of a file and open it
+    OpenFile,
+    NextBuffer,
+    PrevBuffer,
+    CloseBuffer,
+    ListBuffers,
+
+    SplitPane(Orientation),
+    FocusNextPane,
+    ClosePane,
+
+    /// Ask for text to search for, moving to each match as it is typed
+    Search(SearchDirection),
+    /// Search for the last text searched for again, the same way
+    SearchNext,
+    /// Search for the last text searched for again, the other way
+    SearchPrev,
+    /// Replace the matches for a regex in the selection, or in the whole
+    /// buffer if nothing is selected
+    Replace,
+    /// Replace the matches for a regex in a range of lines
+    ReplaceLines,
+
+    ToggleWrap,
+    /// Switch to the next style of line numbers
+    CycleLineNumbers,
+    CycleLineEnding,
+
+    SwitchMode(Mode),
+}
+
+impl Command {
+    /// Get the Command with the given name, as used in the keymap file.
+    ///
+    /// Inserting a char doesn't have a name, as every printable key which
+    /// isn't bound to anything else already inserts itself in insert mode.
+    pub fn from_name(name: &str) -> Option<Command> {
+        let command = match name {
+            "move-up"           => Command::MoveCursor(Direction::Up),
+            "move-down"         => Command::MoveCursor(Direction::Down),
+            "move-left"         => Command::MoveCursor(Direction::Left),
+            "move-right"        => Command::MoveCursor(Direction::Right),
+            "next-word"         => Command::Move(Motion::NextWord),
+            "prev-word"         => Command::Move(Motion::PrevWord),
+            "word-end"          => Command::Move(Motion::WordEnd),
+            "line-start"        => Command::Move(Motion::LineStart),
+            "line-end"          => Command::Move(Motion::LineEnd),
+            "first-non-blank"   => Command::Move(Motion::FirstNonBlank),
+            "next-paragraph"    => Command::Move(Motion::NextParagraph),
+            "prev-paragraph"    => Command::Move(Motion::PrevParagraph),
+            "buffer-start"      => Command::Move(Motion::BufferStart),
+            "buffer-end"        => Command::Move(Motion::BufferEnd),
+            "page-up"           => Command::ScrollPage(Direction::Up),
+            "page-down"         => Command::ScrollPage(Direction::Down),
+            "scroll-up"         => Command::ScrollLine(Direction::Up),
+            "scroll-down"       => Command::ScrollLine(Direction::Down),
+            "scroll-lines"      => Command::ScrollLines,
+            "recenter"          => Command::Recenter,
+            "go-to-line"        => Command::GoToLine,
+
+            "insert-tab"        => Command::InsertTab,
+            "insert-line"       => Command::InsertLine,
+            "delete-backward"   => Command::DeleteChar(Direction::Left),
+            "delete-forward"    => Command::DeleteChar(Direction::Right),
+            "delete-selection"  => Command::DeleteSelection,
+            "delete-line"       => Command::DeleteLine,
+            "indent"            => Command::Indent,
+            "dedent"            => Command::Dedent,
+            "set-mark"          => Command::SetMark,
+
+            "cut"               => Command::Cut,
+            "copy"              => Command::Copy,
+            "paste"             => Command::Paste,
+            "paste-previous"    => Command::PastePrevious,
+
+            "append"            => Command::Append,
+            "open-line-below"   => Command::OpenLineBelow,
+
+            "undo"              => Command::Undo,
+            "redo"              => Command::Redo,
+
+            "save"              => Command::Save,
+            "quit"              => Command::Quit,
+            "force-quit"        => Command::ForceQuit,
+            "resize"            => Command::Resize,
+
+            "open-buffer"       => Command::OpenBuffer,
+            "open-file"         => Command::OpenFile,
+            "next-buffer"       => Command::NextBuffer,
+            "prev-buffer"       => Command::PrevBuffer,
+            "close-buffer"      => Command::CloseBuffer,
+            "list-buffers"      => Command::ListBuffers,
+
+            "split-horizontal"  => Command::SplitPane(Orientation::Horizontal),
+            "split-vertical"    => Command::SplitPane(Orientation::Vertical),
+            "focus-next-pane"   => Command::FocusNextPane,
+            "close-pane"        => Command::ClosePane,
+
+            "search-forward"    => Command::Search(SearchDirection::Forward),
+            "search-backward"   => Command::Search(SearchDirection::Backward),
+            "search-next"       => Command::SearchNext,
+            "search-prev"       => Command::SearchPrev,
+            "replace"           => Command::Replace,
+            "replace-lines"     => Command::ReplaceLines,
+
+            "toggle-wrap"        => Command::ToggleWrap,
+            "cycle-line-numbers" => Command::CycleLineNumbers,
+            "cycle-line-ending"  => Command::CycleLineEnding,
+
+            "normal-mode"       => Command::SwitchMode(Mode::Normal),
+            "insert-mode"       => Command::SwitchMode(Mode::Insert),
+            "visual-mode"       => Command::SwitchMode(Mode::Visual),
+
+            _ => return None,
+        };
+        Some(command)
+    }
+}
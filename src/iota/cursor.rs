@@ -1,7 +1,6 @@
-use std::cell::RefCell;
-
-use buffer::Line;
+use buffer::Buffer;
 
+#[deriving(Clone, PartialEq, Show)]
 pub enum Direction {
     Up,
     Down,
@@ -24,18 +23,59 @@ impl Direction {
     }
 }
 
+/// A way of moving the cursor further than a single char or line
+#[deriving(Clone, PartialEq, Show)]
+pub enum Motion {
+    /// The start of the next word
+    NextWord,
+    /// The start of the word before the cursor
+    PrevWord,
+    /// The last char of the word the cursor is in, or of the next one
+    WordEnd,
+    LineStart,
+    LineEnd,
+    /// The first char on the line which isn't whitespace
+    FirstNonBlank,
+    /// The next blank line after a paragraph
+    NextParagraph,
+    /// The blank line before the paragraph the cursor is in
+    PrevParagraph,
+    BufferStart,
+    BufferEnd,
+}
+
+/// The kinds of char which make up a line. Word motions stop where one kind
+/// gives way to another.
+#[deriving(Clone, PartialEq)]
+enum CharClass {
+    Blank,
+    /// Letters, digits and any of the extra word chars in the Options
+    Word,
+    Punctuation,
+}
+
+/// A grapheme in a line, where it starts and what kind of char it is
+struct Grapheme {
+    offset: uint,
+    class: CharClass,
+}
+
+/// A position in a Buffer.
+///
+/// The cursor only stores the line number and the offset into that line, so
+/// that several Views can each keep a cursor into the same Buffer.
 #[deriving(Clone)]
-pub struct Cursor<'c> {
+pub struct Cursor {
     pub offset: uint,
-    line: Option<&'c RefCell<Line>>,
+    linenum: uint,
 }
 
-impl<'c> Cursor<'c> {
+impl Cursor {
     /// Create a new cursor instance
-    pub fn new() -> Cursor<'c> {
+    pub fn new() -> Cursor {
         Cursor {
             offset: 0,
-            line: None,
+            linenum: 0,
         }
     }
 
@@ -44,7 +84,7 @@ impl<'c> Cursor<'c> {
     }
 
     pub fn get_linenum(&self) -> uint {
-        self.line.unwrap().borrow().linenum
+        self.linenum
     }
 
     pub fn get_offset(&self) -> uint {
@@ -55,59 +95,198 @@ impl<'c> Cursor<'c> {
         self.offset = offset;
     }
 
-    pub fn set_line(&mut self, line: Option<&'c RefCell<Line>>) {
-        self.line = line;
+    pub fn set_line(&mut self, linenum: uint, buffer: &Buffer) {
+        self.linenum = linenum;
 
-        // check that the current offset is longer than the length of the line
+        // check that the current offset is longer than the length of the line,
+        // and that it doesn't land in the middle of a grapheme
         let offset = self.get_offset();
-        let line_length = self.get_line().borrow().len();
-        if offset > line_length {
-            self.set_offset(line_length);
-        }
+        let boundary = buffer.get_line(linenum).grapheme_boundary_at(offset);
+        self.set_offset(boundary);
     }
 
-    pub fn get_line(&self) -> &'c RefCell<Line> {
-        self.line.unwrap()
+    pub fn get_line_length(&self, buffer: &Buffer) -> uint {
+        buffer.get_line(self.linenum).len()
     }
 
-    pub fn get_line_length(&self) -> uint {
-        self.get_line().borrow().len()
-    }
-
-    pub fn delete_backward_char(&mut self) {
+    /// Delete the grapheme before the cursor, returning the deleted text
+    pub fn delete_backward_char(&mut self, buffer: &mut Buffer) -> String {
         let offset = self.get_offset();
-        let line = self.get_line();
-        line.borrow_mut().data.remove(offset-1);
-        self.set_offset(offset-1);
+        let prev = buffer.get_line(self.linenum).prev_grapheme_boundary(offset);
+        let removed = buffer.remove_text((prev, self.linenum), (offset, self.linenum));
+        self.set_offset(prev);
+        removed
     }
 
-    pub fn delete_forward_char(&mut self) {
+    /// Delete the grapheme after the cursor, returning the deleted text
+    pub fn delete_forward_char(&mut self, buffer: &mut Buffer) -> String {
         let offset = self.get_offset();
-        let line = self.get_line();
-        line.borrow_mut().data.remove(offset);
+        let next = buffer.get_line(self.linenum).next_grapheme_boundary(offset);
+        let removed = buffer.remove_text((offset, self.linenum), (next, self.linenum));
         self.set_offset(offset);
+        removed
     }
 
-    pub fn insert_char(&mut self, ch: char) {
+    pub fn insert_char(&mut self, buffer: &mut Buffer, ch: char) {
         let offset = self.get_offset();
-        let line = self.get_line();
-        line.borrow_mut().data.insert(offset, ch as u8);
-        self.set_offset(offset+1)
+        let (offset, _) = buffer.insert_text(offset, self.linenum, ch.to_string().as_slice());
+        self.set_offset(offset)
     }
 
-    pub fn move_right(&mut self) {
-        let line_len = self.get_line().borrow().len();
+    pub fn move_right(&mut self, buffer: &Buffer) {
         let current_offset = self.get_offset();
-        if line_len > current_offset {
-            self.set_offset(current_offset + 1);
-        }
+        let next = buffer.get_line(self.linenum).next_grapheme_boundary(current_offset);
+        self.set_offset(next);
     }
 
-    pub fn move_left(&mut self) {
+    pub fn move_left(&mut self, buffer: &Buffer) {
         let current_offset = self.get_offset();
-        if current_offset > 0 {
-            self.set_offset(current_offset - 1);
+        let prev = buffer.get_line(self.linenum).prev_grapheme_boundary(current_offset);
+        self.set_offset(prev);
+    }
+
+    /// Move to the start of the next word, or to the start of an empty line
+    /// if one comes first. Words are runs of letters, digits and `word_chars`,
+    /// or runs of other chars which aren't whitespace.
+    pub fn move_to_next_word(&mut self, buffer: &Buffer, word_chars: &str) {
+        let mut linenum = self.linenum;
+        let mut graphemes = classify_line(buffer, linenum, word_chars);
+        let mut index = index_at(graphemes.as_slice(), self.offset);
+
+        // skip the rest of the word under the cursor
+        if index < graphemes.len() && graphemes[index].class != CharClass::Blank {
+            let class = graphemes[index].class.clone();
+            while index < graphemes.len() && graphemes[index].class == class { index += 1 }
+        }
+
+        // and then the whitespace after it, which may run onto the next lines
+        loop {
+            while index < graphemes.len() && graphemes[index].class == CharClass::Blank { index += 1 }
+            if index < graphemes.len() { break }
+
+            if linenum + 1 == buffer.line_count() {
+                let line_len = buffer.get_line(linenum).len();
+                return self.set_position(line_len, linenum)
+            }
+            linenum += 1;
+            graphemes = classify_line(buffer, linenum, word_chars);
+            index = 0;
+            if graphemes.is_empty() { break }
         }
+
+        let offset = graphemes.get(index).map_or(0, |grapheme| grapheme.offset);
+        self.set_position(offset, linenum);
+    }
+
+    /// Move to the start of the word before the cursor, or of the word the
+    /// cursor is in, stopping at any empty line on the way
+    pub fn move_to_prev_word(&mut self, buffer: &Buffer, word_chars: &str) {
+        let mut linenum = self.linenum;
+        let mut graphemes = classify_line(buffer, linenum, word_chars);
+        let mut index = index_at(graphemes.as_slice(), self.offset);
+
+        // skip back over the whitespace before the cursor
+        loop {
+            while index > 0 && graphemes[index - 1].class == CharClass::Blank { index -= 1 }
+            if index > 0 { break }
+
+            if linenum == 0 { return self.set_position(0, 0) }
+            linenum -= 1;
+            graphemes = classify_line(buffer, linenum, word_chars);
+            index = graphemes.len();
+            if graphemes.is_empty() { return self.set_position(0, linenum) }
+        }
+
+        // and then to the start of the word before it
+        let class = graphemes[index - 1].class.clone();
+        while index > 0 && graphemes[index - 1].class == class { index -= 1 }
+        self.set_position(graphemes[index].offset, linenum);
+    }
+
+    /// Move onto the last char of the word the cursor is in, or of the next
+    /// word if the cursor is already at the end of one
+    pub fn move_to_word_end(&mut self, buffer: &Buffer, word_chars: &str) {
+        let mut linenum = self.linenum;
+        let mut graphemes = classify_line(buffer, linenum, word_chars);
+        let mut index = index_at(graphemes.as_slice(), self.offset) + 1;
+
+        // skip the whitespace after the cursor, which may run onto the next
+        // lines
+        loop {
+            while index < graphemes.len() && graphemes[index].class == CharClass::Blank { index += 1 }
+            if index < graphemes.len() { break }
+
+            if linenum + 1 == buffer.line_count() {
+                let line_len = buffer.get_line(linenum).len();
+                return self.set_position(line_len, linenum)
+            }
+            linenum += 1;
+            graphemes = classify_line(buffer, linenum, word_chars);
+            index = 0;
+        }
+
+        let class = graphemes[index].class.clone();
+        while index + 1 < graphemes.len() && graphemes[index + 1].class == class { index += 1 }
+        self.set_position(graphemes[index].offset, linenum);
+    }
+
+    pub fn move_to_line_start(&mut self) {
+        self.set_offset(0);
+    }
+
+    pub fn move_to_line_end(&mut self, buffer: &Buffer) {
+        let line_len = self.get_line_length(buffer);
+        self.set_offset(line_len);
+    }
+
+    /// Move to the first char on the line which isn't whitespace, or to the
+    /// end of the line if there isn't one
+    pub fn move_to_first_non_blank(&mut self, buffer: &Buffer) {
+        let line = buffer.get_line(self.linenum);
+        let offset = line.data.as_slice().char_indices()
+                         .find(|&(_, ch)| !ch.is_whitespace())
+                         .map_or(line.len(), |(offset, _)| offset);
+        self.set_offset(offset);
+    }
+
+    /// Move to the start of the first blank line after the next paragraph,
+    /// or to the end of the buffer if there isn't one
+    pub fn move_to_next_paragraph(&mut self, buffer: &Buffer) {
+        let line_count = buffer.line_count();
+        let mut linenum = self.linenum;
+        while linenum < line_count && is_blank_line(buffer, linenum) { linenum += 1 }
+        while linenum < line_count && !is_blank_line(buffer, linenum) { linenum += 1 }
+
+        if linenum == line_count {
+            self.move_to_buffer_end(buffer);
+        } else {
+            self.set_position(0, linenum);
+        }
+    }
+
+    /// Move to the start of the last blank line before the previous
+    /// paragraph, or to the start of the buffer if there isn't one
+    pub fn move_to_prev_paragraph(&mut self, buffer: &Buffer) {
+        let mut linenum = self.linenum;
+        while linenum > 0 && is_blank_line(buffer, linenum) { linenum -= 1 }
+        while linenum > 0 && !is_blank_line(buffer, linenum) { linenum -= 1 }
+        self.set_position(0, linenum);
+    }
+
+    pub fn move_to_buffer_start(&mut self) {
+        self.set_position(0, 0);
+    }
+
+    pub fn move_to_buffer_end(&mut self, buffer: &Buffer) {
+        let last_line = buffer.line_count() - 1;
+        let line_len = buffer.get_line(last_line).len();
+        self.set_position(line_len, last_line);
+    }
+
+    // the offset must already be at a grapheme boundary within the line
+    fn set_position(&mut self, offset: uint, linenum: uint) {
+        self.linenum = linenum;
+        self.offset = offset;
     }
 
     pub fn get_status_text(&self) -> String {
@@ -116,68 +295,141 @@ impl<'c> Cursor<'c> {
     }
 }
 
+/// Work out whether `ch` is whitespace, part of a word or punctuation
+fn char_class(ch: char, word_chars: &str) -> CharClass {
+    if ch.is_whitespace() {
+        CharClass::Blank
+    } else if ch.is_alphanumeric() || word_chars.contains_char(ch) {
+        CharClass::Word
+    } else {
+        CharClass::Punctuation
+    }
+}
+
+/// Split a line of the buffer into its graphemes, each classed by its first
+/// char
+fn classify_line(buffer: &Buffer, linenum: uint, word_chars: &str) -> Vec<Grapheme> {
+    buffer.get_line(linenum).data.as_slice().grapheme_indices(true).map(|(offset, grapheme)| {
+        Grapheme { offset: offset, class: char_class(grapheme.char_at(0), word_chars) }
+    }).collect()
+}
+
+/// Get the index of the grapheme starting at `offset`, or the number of
+/// graphemes if it is at the end of the line
+fn index_at(graphemes: &[Grapheme], offset: uint) -> uint {
+    graphemes.iter().position(|grapheme| grapheme.offset >= offset).unwrap_or(graphemes.len())
+}
+
+/// Check whether a line of the buffer is empty or only has whitespace on it
+fn is_blank_line(buffer: &Buffer, linenum: uint) -> bool {
+    buffer.get_line(linenum).data.as_slice().chars().all(|ch| ch.is_whitespace())
+}
+
 
 #[cfg(test)]
 mod tests {
 
-    use std::cell::RefCell;
-
     use cursor::Cursor;
-    use buffer::Line;
+    use buffer::{Buffer, Line};
+    use rope::Rope;
     use utils::data_from_str;
 
-    fn setup_cursor<'c>() -> Cursor<'c> {
+    fn setup_buffer() -> Buffer {
+        let mut buffer = Buffer::new();
+        buffer.lines = Rope::from_vec(vec!(
+            Line::new(data_from_str("first")),
+            Line::new(data_from_str("test")),
+        ));
+        buffer
+    }
+
+    fn buffer_with_lines(lines: &[&str]) -> Buffer {
+        let mut buffer = Buffer::new();
+        buffer.lines = Rope::from_vec(lines.iter().map(|text| Line::new(data_from_str(*text))).collect());
+        buffer
+    }
+
+    fn setup_cursor(buffer: &Buffer) -> Cursor {
         let mut cursor = Cursor::new();
-        let line = RefCell::new(Line::new(data_from_str("test"), 1));
-        cursor.set_line(Some(&line));
+        cursor.set_line(1, buffer);
         return cursor
     }
 
     #[test]
     fn test_moving_right() {
-        let mut cursor = setup_cursor();
+        let buffer = setup_buffer();
+        let mut cursor = setup_cursor(&buffer);
 
         assert_eq!(cursor.offset, 0);
-        cursor.move_right();
+        cursor.move_right(&buffer);
         assert_eq!(cursor.offset, 1);
     }
 
     #[test]
     fn test_moving_left() {
-        let mut cursor = setup_cursor();
+        let buffer = setup_buffer();
+        let mut cursor = setup_cursor(&buffer);
         cursor.set_offset(1);
 
         assert_eq!(cursor.offset, 1);
-        cursor.move_left();
+        cursor.move_left(&buffer);
         assert_eq!(cursor.offset, 0);
     }
 
+    #[test]
+    fn moving_steps_over_whole_graphemes() {
+        let mut buffer = setup_buffer();
+        buffer.get_line_mut(1).data = data_from_str("漢e\u0301");
+        let mut cursor = setup_cursor(&buffer);
+
+        cursor.move_right(&buffer);
+        assert_eq!(cursor.offset, 3);
+        cursor.move_right(&buffer);
+        assert_eq!(cursor.offset, 6);
+        cursor.move_left(&buffer);
+        assert_eq!(cursor.offset, 3);
+    }
+
+    #[test]
+    fn setting_line_does_not_land_inside_a_char() {
+        let mut buffer = setup_buffer();
+        buffer.get_line_mut(1).data = data_from_str("a漢字");
+        let mut cursor = Cursor::new();
+
+        cursor.set_offset(2);
+        cursor.set_line(1, &buffer);
+
+        assert_eq!(cursor.offset, 1);
+    }
+
     #[test]
     fn test_get_position() {
+        let buffer = setup_buffer();
         let mut cursor = Cursor::new();
-        let line = RefCell::new(Line::new(data_from_str("test"), 1));
-        cursor.set_line(Some(&line));
+        cursor.set_line(1, &buffer);
         assert_eq!(cursor.get_position(), (0, 1));
     }
 
     #[test]
     fn test_get_linenum() {
+        let buffer = setup_buffer();
         let mut cursor = Cursor::new();
-        let line = RefCell::new(Line::new(data_from_str("test"), 1));
-        cursor.set_line(Some(&line));
+        cursor.set_line(1, &buffer);
 
         assert_eq!(cursor.get_linenum(), 1);
     }
 
     #[test]
     fn test_get_offset() {
-        let cursor = setup_cursor();
+        let buffer = setup_buffer();
+        let cursor = setup_cursor(&buffer);
         assert_eq!(cursor.get_offset(), 0)
     }
 
     #[test]
     fn test_set_offset() {
-        let mut cursor = setup_cursor();
+        let buffer = setup_buffer();
+        let mut cursor = setup_cursor(&buffer);
         cursor.set_offset(3);
 
         assert_eq!(cursor.offset, 3);
@@ -186,62 +438,186 @@ mod tests {
 
     #[test]
     fn test_moving_to_end_of_line_when_set() {
+        let buffer = setup_buffer();
         let mut cursor = Cursor::new();
-        let line = RefCell::new(Line::new(data_from_str("test"), 1));
 
         cursor.set_offset(10);
-        cursor.set_line(Some(&line));
+        cursor.set_line(1, &buffer);
 
         assert_eq!(cursor.offset, 4);
     }
 
     #[test]
     fn test_get_line_length() {
+        let buffer = setup_buffer();
         let mut cursor = Cursor::new();
-        let line = RefCell::new(Line::new(data_from_str("test"), 1));
 
-        cursor.set_line(Some(&line));
+        cursor.set_line(1, &buffer);
 
-        assert_eq!(cursor.get_line_length(), 4);
+        assert_eq!(cursor.get_line_length(&buffer), 4);
     }
 
     #[test]
     fn test_delete_backward_char() {
+        let mut buffer = setup_buffer();
         let mut cursor = Cursor::new();
-        let line = RefCell::new(Line::new(data_from_str("test"), 1));
 
-        cursor.set_line(Some(&line));
+        cursor.set_line(1, &buffer);
         cursor.set_offset(1);
-        cursor.delete_backward_char();
+        cursor.delete_backward_char(&mut buffer);
+
+        assert_eq!(buffer.get_line(1).data, data_from_str("est"));
+    }
+
+    #[test]
+    fn deleting_removes_a_whole_multibyte_char() {
+        let mut buffer = setup_buffer();
+        buffer.get_line_mut(1).data = data_from_str("t漢t");
+        let mut cursor = Cursor::new();
+
+        cursor.set_line(1, &buffer);
+        cursor.set_offset(4);
+        let removed = cursor.delete_backward_char(&mut buffer);
 
-        assert_eq!(line.borrow().data, data_from_str("est"));
+        assert_eq!(removed, data_from_str("漢"));
+        assert_eq!(cursor.offset, 1);
+        assert_eq!(buffer.get_line(1).data, data_from_str("tt"));
     }
 
     #[test]
     fn test_delete_forward_char() {
+        let mut buffer = setup_buffer();
         let mut cursor = Cursor::new();
-        let line = RefCell::new(Line::new(data_from_str("test"), 1));
 
-        cursor.set_line(Some(&line));
-        cursor.delete_forward_char();
+        cursor.set_line(1, &buffer);
+        cursor.delete_forward_char(&mut buffer);
 
-        assert_eq!(line.borrow().data, data_from_str("est"));
+        assert_eq!(buffer.get_line(1).data, data_from_str("est"));
     }
 
     #[test]
     fn test_insert_char() {
+        let mut buffer = setup_buffer();
         let mut cursor = Cursor::new();
-        let line = RefCell::new(Line::new(data_from_str("test"), 1));
 
-        cursor.set_line(Some(&line));
-        cursor.insert_char('x');
+        cursor.set_line(1, &buffer);
+        cursor.insert_char(&mut buffer, 'x');
+
+        assert_eq!(buffer.get_line(1).data, data_from_str("xtest"));
+    }
+
+    #[test]
+    fn moving_by_words_stops_at_words_and_punctuation() {
+        let buffer = buffer_with_lines(&["let x_y = f(1);", "  next"]);
+        let mut cursor = Cursor::new();
+        let mut stops = Vec::new();
+        for _ in range(0, 6) {
+            cursor.move_to_next_word(&buffer, "_");
+            stops.push(cursor.get_position());
+        }
+        assert_eq!(stops, vec![(4, 0), (8, 0), (10, 0), (11, 0), (12, 0), (13, 0)]);
+
+        cursor.move_to_next_word(&buffer, "_");
+        assert_eq!(cursor.get_position(), (2, 1));
+        cursor.move_to_next_word(&buffer, "_");
+        assert_eq!(cursor.get_position(), (6, 1));
+
+        cursor.move_to_prev_word(&buffer, "_");
+        assert_eq!(cursor.get_position(), (2, 1));
+        cursor.move_to_prev_word(&buffer, "_");
+        assert_eq!(cursor.get_position(), (13, 0));
+    }
+
+    #[test]
+    fn word_chars_can_be_changed() {
+        let buffer = buffer_with_lines(&["foo-bar_baz"]);
+        let mut cursor = Cursor::new();
+
+        cursor.move_to_next_word(&buffer, "_");
+        assert_eq!(cursor.get_offset(), 3);
+
+        cursor.set_offset(0);
+        cursor.move_to_next_word(&buffer, "-_");
+        assert_eq!(cursor.get_offset(), 11);
+
+        cursor.move_to_prev_word(&buffer, "-");
+        assert_eq!(cursor.get_offset(), 8);
+    }
+
+    #[test]
+    fn moving_by_words_stops_at_empty_lines() {
+        let buffer = buffer_with_lines(&["one", "", "two"]);
+        let mut cursor = Cursor::new();
+
+        cursor.move_to_next_word(&buffer, "_");
+        assert_eq!(cursor.get_position(), (0, 1));
+        cursor.move_to_next_word(&buffer, "_");
+        assert_eq!(cursor.get_position(), (0, 2));
+        cursor.move_to_prev_word(&buffer, "_");
+        assert_eq!(cursor.get_position(), (0, 1));
+    }
+
+    #[test]
+    fn moving_to_word_end_skips_to_the_next_word() {
+        let buffer = buffer_with_lines(&["one  two", "", "漢字 x"]);
+        let mut cursor = Cursor::new();
+
+        cursor.move_to_word_end(&buffer, "_");
+        assert_eq!(cursor.get_position(), (2, 0));
+        cursor.move_to_word_end(&buffer, "_");
+        assert_eq!(cursor.get_position(), (7, 0));
+        cursor.move_to_word_end(&buffer, "_");
+        assert_eq!(cursor.get_position(), (3, 2));
+    }
+
+    #[test]
+    fn moving_to_line_boundaries() {
+        let buffer = buffer_with_lines(&["  \tindented  "]);
+        let mut cursor = Cursor::new();
+
+        cursor.move_to_first_non_blank(&buffer);
+        assert_eq!(cursor.get_offset(), 3);
+        cursor.move_to_line_end(&buffer);
+        assert_eq!(cursor.get_offset(), 13);
+        cursor.move_to_line_start();
+        assert_eq!(cursor.get_offset(), 0);
+    }
+
+    #[test]
+    fn moving_by_paragraphs_stops_at_blank_lines() {
+        let buffer = buffer_with_lines(&["one", "two", "", "  ", "three", "", "four"]);
+        let mut cursor = Cursor::new();
+
+        cursor.move_to_next_paragraph(&buffer);
+        assert_eq!(cursor.get_position(), (0, 2));
+        cursor.move_to_next_paragraph(&buffer);
+        assert_eq!(cursor.get_position(), (0, 5));
+        cursor.move_to_next_paragraph(&buffer);
+        assert_eq!(cursor.get_position(), (4, 6));
+
+        cursor.move_to_prev_paragraph(&buffer);
+        assert_eq!(cursor.get_position(), (0, 5));
+        cursor.move_to_prev_paragraph(&buffer);
+        assert_eq!(cursor.get_position(), (0, 3));
+        cursor.move_to_prev_paragraph(&buffer);
+        assert_eq!(cursor.get_position(), (0, 0));
+    }
+
+    #[test]
+    fn moving_to_buffer_boundaries() {
+        let buffer = setup_buffer();
+        let mut cursor = Cursor::new();
 
-        assert_eq!(line.borrow().data, data_from_str("xtest"));
+        cursor.move_to_buffer_end(&buffer);
+        assert_eq!(cursor.get_position(), (4, 1));
+        cursor.move_to_buffer_start();
+        assert_eq!(cursor.get_position(), (0, 0));
     }
 
     #[test]
     fn test_get_status_text() {
-        let cursor = setup_cursor();
+        let buffer = setup_buffer();
+        let cursor = setup_cursor(&buffer);
         assert_eq!(cursor.get_status_text(), "(0, 1)".to_string());
     }
 
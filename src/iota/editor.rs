@@ -1,14 +1,59 @@
 extern crate rustbox;
 
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::comm::{Receiver, Sender};
+use std::cmp;
 use std::num;
-use std::io::{File, FileMode, FileAccess};
+use std::num::Int;
+use std::rc::Rc;
+
+use regex::Regex;
 
 use super::Response;
+use buffer::{Buffer, LineEnding};
+use clipboard;
+use clipboard::{KillRing, SystemClipboard};
+use command::Command;
 use input::Input;
 use cursor::Direction;
+use keyboard;
 use keyboard::Key;
-use view::View;
+use keymap::{Keymap, Lookup};
+use layout::{Layout, Orientation, Rect};
+use modes::Mode;
+use options::Options;
+use prompt::{History, Prompt, PromptAction, PromptStatus};
+use replace;
+use replace::Replace;
+use search;
+use search::SearchDirection;
+use terminal;
+use terminal::Event;
+use uibuf::UIBuffer;
+use view;
+use view::{Region, View};
+
+use utils;
+
+
+/// Get the name of an input, to say which one couldn't be opened
+fn input_name(source: &Input) -> String {
+    match *source {
+        Input::Filename(Some(ref name)) => name.clone(),
+        Input::Filename(None)           => String::from_str("untitled"),
+        Input::Stdin(_)                 => String::from_str("standard input"),
+    }
+}
+
+/// Check whether two handles refer to the same Buffer
+fn same_buffer(a: &Rc<RefCell<Buffer>>, b: &Rc<RefCell<Buffer>>) -> bool {
+    (&**a as *const RefCell<Buffer>) == (&**b as *const RefCell<Buffer>)
+}
+
+
+// how many lines a turn of the mouse wheel scrolls by
+const MOUSE_WHEEL_LINES: int = 3;
 
 
 enum EventStatus {
@@ -17,65 +62,866 @@ enum EventStatus {
 }
 
 
-pub struct Editor<'e> {
+/// A search whose text is still being typed into the prompt
+struct Search {
+    direction: SearchDirection,
+    // the active pane as it was when the search started, which is restored
+    // if the search is cancelled
+    origin: View,
+}
+
+
+pub struct Editor {
     pub running: bool,
-    pub sender: Sender<rustbox::Event>,
+    pub sender: Sender<Event>,
+
+    events: Receiver<Event>,
+    uibuf: UIBuffer,
+    options: Options,
+
+    // the last View into every open buffer, so that the cursor position and
+    // scroll offset are kept when switching between them
+    buffers: Vec<View>,
+
+    // the Views currently on screen, arranged by the layout
+    panes: Vec<View>,
+    layout: Layout,
+    active_pane: uint,
+
+    // shown in place of the status bar until the next key press
+    message: Option<String>,
+
+    // text input shown in place of the status bar of the active pane
+    prompt: Option<Prompt>,
+    prompt_history: HashMap<PromptAction, History>,
+
+    search: Option<Search>,
+    // the text and direction of the last search which was finished
+    last_search: Option<(String, SearchDirection)>,
+
+    // the region and regex to replace, while the rest is being asked for
+    replace_region: Option<Region>,
+    replace_regex: Option<Regex>,
+    // a replace waiting for each match to be confirmed
+    replace: Option<Replace>,
+
+    // the pane the left mouse button was pressed in and the position in its
+    // buffer, while it is held down, so that moving the mouse before it is
+    // released selects text
+    mouse_press: Option<(uint, (uint, uint))>,
+
+    // text cut and copied from any buffer
+    kill_ring: KillRing,
+    // shares the kill ring with the rest of the desktop
+    clipboard: SystemClipboard,
+    // the pane, and the text put in, if the last command was a paste, along
+    // with how far back in the kill ring it came from
+    last_paste: Option<(uint, Region, uint)>,
 
-    events: Receiver<rustbox::Event>,
-    view: View<'e>,
+    // decides what each key press does
+    mode: Mode,
+    keymap: Keymap,
+    // the start of a multi-key binding, typed so far
+    pending_keys: Vec<Key>,
 }
 
-impl<'e> Editor<'e> {
-    pub fn new(source: Input) -> Editor<'e> {
-        let view = View::new(source);
+impl Editor {
+    pub fn new(source: Input, options: Options, clipboard: SystemClipboard) -> Editor {
+        let height: uint = utils::get_term_height();
+        let width: uint = utils::get_term_width();
+        Editor::new_with_size(source, options, clipboard, width, height)
+    }
+
+    /// Create an editor for a screen of the given size
+    fn new_with_size(source: Input, options: Options, clipboard: SystemClipboard,
+                     width: uint, height: uint) -> Editor {
+        let uibuf = UIBuffer::new(width, height);
+
+        // an empty buffer is shown in place of a file which can't be read
+        let name = input_name(&source);
+        let mut messages = Vec::new();
+        let buffer = match Buffer::new_from_input(source) {
+            Ok(buffer) => buffer,
+            Err(error) => {
+                messages.push(format!("Could not open {}: {}", name, error));
+                Buffer::new_empty()
+            }
+        };
+        if let Some(warning) = buffer.get_line_ending_warning() {
+            messages.push(warning);
+        }
+        let view = View::new(Rc::new(RefCell::new(buffer)), options.clone(), width, height);
+
+        let (keymap, errors) = Keymap::from_user_config();
+        if !errors.is_empty() {
+            messages.push(format!("Errors in keymap: {}", errors.connect("; ")));
+        }
+        let message = if messages.is_empty() { None } else { Some(messages.connect("; ")) };
 
         let (send, recv) = channel();
         Editor {
             sender: send,
             events: recv,
-            view: view,
+            uibuf: uibuf,
+            options: options,
+            buffers: vec![view.clone()],
+            panes: vec![view],
+            layout: Layout::Pane(0),
+            active_pane: 0,
+            message: message,
+            prompt: None,
+            prompt_history: HashMap::new(),
+            search: None,
+            last_search: None,
+            replace_region: None,
+            replace_regex: None,
+            replace: None,
+            mouse_press: None,
+            kill_ring: KillRing::new(),
+            clipboard: clipboard,
+            last_paste: None,
+            mode: Mode::Insert,
+            keymap: keymap,
+            pending_keys: Vec::new(),
             running: false,
         }
     }
 
+    /// Add a new buffer to the list of open buffers, without switching to it.
+    ///
+    /// Returns false, with the reason shown in the status bar, if the input
+    /// can't be read.
+    pub fn add_buffer(&mut self, source: Input) -> bool {
+        let name = input_name(&source);
+        let buffer = match Buffer::new_from_input(source) {
+            Ok(buffer) => buffer,
+            Err(error) => {
+                self.message = Some(format!("Could not open {}: {}", name, error));
+                return false
+            }
+        };
+        if let Some(warning) = buffer.get_line_ending_warning() {
+            self.message = Some(warning);
+        }
+        let view = View::new(Rc::new(RefCell::new(buffer)), self.options.clone(),
+                             self.uibuf.get_width(), self.uibuf.get_height());
+        self.buffers.push(view);
+        true
+    }
+
+    /// Add a new buffer to the list of open buffers and show it in the
+    /// active pane
+    pub fn open_buffer(&mut self, source: Input) {
+        if self.add_buffer(source) {
+            let index = self.buffers.len() - 1;
+            self.show_buffer(index);
+        }
+    }
+
+    /// Switch to the next buffer, wrapping around to the first
+    pub fn next_buffer(&mut self) {
+        let index = (self.active_buffer_index() + 1) % self.buffers.len();
+        self.show_buffer(index);
+    }
+
+    /// Switch to the previous buffer, wrapping around to the last
+    pub fn prev_buffer(&mut self) {
+        let mut index = self.active_buffer_index();
+        if index == 0 {
+            index = self.buffers.len();
+        }
+        self.show_buffer(index - 1);
+    }
+
+    /// Close the active buffer, first asking whether to if it has unsaved
+    /// changes
+    pub fn close_buffer(&mut self) {
+        let label = {
+            let buffer = self.view().buffer.borrow();
+            if !buffer.is_modified() {
+                None
+            } else {
+                Some(format!("{} has unsaved changes, close it anyway? (y/n) ", buffer.get_name()))
+            }
+        };
+        match label {
+            Some(label) => self.ask(label.as_slice(), PromptAction::ConfirmClose),
+            None        => self.discard_buffer(),
+        }
+    }
+
+    /// Close the active buffer and switch to the one before it, throwing
+    /// away any unsaved changes.
+    ///
+    /// Every pane showing the buffer switches too. Closing the last open
+    /// buffer replaces it with an empty one.
+    fn discard_buffer(&mut self) {
+        let index = self.active_buffer_index();
+        let closed = self.buffers[index].buffer.clone();
+        self.buffers.remove(index);
+
+        if self.buffers.is_empty() {
+            self.add_buffer(Input::Filename(None));
+        }
+
+        let replacement = if index > 0 { index - 1 } else { 0 };
+        for pane in self.panes.iter_mut() {
+            if same_buffer(&pane.buffer, &closed) {
+                *pane = self.buffers[replacement].clone();
+            }
+        }
+    }
+
+    /// Show the list of open buffers in the status bar.
+    ///
+    /// The buffer in the active pane is marked with a '*'.
+    pub fn list_buffers(&mut self) {
+        let active = self.active_buffer_index();
+        let mut entries = Vec::new();
+        for (index, view) in self.buffers.iter().enumerate() {
+            let marker = if index == active { "*" } else { "" };
+            entries.push(format!("{}{}: {}", marker, index + 1, view.buffer.borrow().get_name()));
+        }
+        self.message = Some(entries.connect("  "));
+    }
+
+    /// Show the buffer at `index` in the list of open buffers in the active
+    /// pane, remembering the position in the buffer being switched away from.
+    fn show_buffer(&mut self, index: uint) {
+        let current = self.active_buffer_index();
+        let view = self.view().clone();
+        self.buffers[current] = view;
+
+        let view = self.buffers[index].clone();
+        *self.view_mut() = view;
+    }
+
+    fn active_buffer_index(&self) -> uint {
+        let view = self.view();
+        self.buffers.iter()
+                    .position(|v| same_buffer(&v.buffer, &view.buffer))
+                    .unwrap_or(0)
+    }
+
+    /// Split the active pane in two, with both halves showing the same buffer.
+    ///
+    /// The new pane becomes the active one.
+    pub fn split_pane(&mut self, orientation: Orientation) {
+        let active_pane = self.active_pane;
+        let area = self.arrange_panes().into_iter().find(|&(pane, _)| pane == active_pane);
+        if let Some((_, rect)) = area {
+            if !rect.can_split(&orientation) {
+                self.message = Some(String::from_str("Not enough room to split the pane"));
+                return
+            }
+        }
+
+        let new_pane = self.panes.len();
+        let view = self.view().clone();
+        self.panes.push(view);
+        self.layout.split(self.active_pane, new_pane, orientation);
+        self.active_pane = new_pane;
+    }
+
+    /// Close the active pane, giving its space to its neighbour
+    pub fn close_pane(&mut self) {
+        if self.panes.len() == 1 {
+            self.message = Some(String::from_str("Can't close the only pane"));
+            return
+        }
+
+        // remember the position in the buffer for when it is shown again
+        let current = self.active_buffer_index();
+        let view = self.view().clone();
+        self.buffers[current] = view;
+
+        let closed = self.active_pane;
+        self.panes.remove(closed);
+        self.layout.remove(closed);
+
+        let panes = self.layout.panes();
+        self.active_pane = panes[0];
+    }
+
+    /// Move focus to the next pane on screen, wrapping around to the first
+    pub fn focus_next_pane(&mut self) {
+        let panes = self.layout.panes();
+        let position = panes.iter().position(|&p| p == self.active_pane).unwrap_or(0);
+        self.active_pane = panes[(position + 1) % panes.len()];
+    }
+
+    /// Recreate the UIBuffer at the current size of the terminal
+    pub fn resize(&mut self) {
+        let height: uint = utils::get_term_height();
+        let width: uint = utils::get_term_width();
+        self.uibuf = UIBuffer::new(width, height);
+    }
+
+    fn view(&self) -> &View {
+        &self.panes[self.active_pane]
+    }
+
+    fn view_mut(&mut self) -> &mut View {
+        &mut self.panes[self.active_pane]
+    }
+
     pub fn handle_key_event(&mut self, key: u16, ch: u32) -> Response {
+        self.message = None;
+
         let key_code = key as u32 + ch;
         let input_key: Option<Key> = num::from_u32(key_code);
 
+        if self.prompt.is_some() {
+            return self.handle_prompt_key(input_key)
+        }
+
         match self.handle_system_event(input_key) {
             EventStatus::Handled(response) => { response }
             EventStatus::NotHandled        => { Response::Continue }
         }
     }
 
-    pub fn save_active_buffer(&mut self) {
-        let lines = &self.view.buffer.lines;
-        let path = Path::new(&self.view.buffer.file_path);
+    /// Switch the active buffer to the next style of line ending, which is
+    /// used for every line when it is saved
+    pub fn cycle_line_ending(&mut self) {
+        let handle = self.view().buffer.clone();
+        let mut buffer = handle.borrow_mut();
 
-        let mut file = match File::open_mode(&path, FileMode::Open, FileAccess::Write) {
-            Ok(f) => f,
-            Err(e) => panic!("file error: {}", e),
+        let line_ending = match buffer.line_ending {
+            LineEnding::Lf   => LineEnding::CrLf,
+            LineEnding::CrLf => LineEnding::Cr,
+            LineEnding::Cr   => LineEnding::Lf,
         };
+        buffer.set_line_ending(line_ending);
+        self.message = Some(format!("Line endings set to {}", buffer.line_ending.name()));
+    }
+
+    /// Switch the active pane to the next style of line numbers
+    pub fn cycle_line_numbers(&mut self) {
+        let line_numbers = self.view_mut().cycle_line_numbers();
+        self.message = Some(format!("Line numbers: {}", line_numbers.name()));
+    }
+
+    /// Ask for a line of text in place of the status line of the active
+    /// pane, which is passed to `action` once it is confirmed.
+    ///
+    /// Text confirmed in earlier prompts for the same action can be brought
+    /// back with Up and Down.
+    fn ask(&mut self, label: &str, action: PromptAction) {
+        let history = match self.prompt_history.get(&action) {
+            Some(history) => history.clone(),
+            None          => History::new(),
+        };
+        self.prompt = Some(Prompt::new(label, action, history));
+    }
+
+    /// Send a key press to the open prompt
+    fn handle_prompt_key(&mut self, k: Option<Key>) -> Response {
+        let key = match k {
+            Some(k) => k,
+            None => return Response::Continue
+        };
+
+        if self.replace.is_some() {
+            self.handle_replace_key(key);
+            return Response::Continue
+        }
+
+        let action = match self.prompt {
+            Some(ref prompt) => prompt.action.clone(),
+            None             => return Response::Continue,
+        };
+        if action == PromptAction::ConfirmQuit || action == PromptAction::ConfirmClose {
+            return self.handle_confirm_key(action, key)
+        }
+
+        // while searching, C-s and C-r move on to the next or previous match
+        if self.search.is_some() {
+            let direction = match key {
+                keyboard::CTRL_S => Some(SearchDirection::Forward),
+                keyboard::CTRL_R => Some(SearchDirection::Backward),
+                _                => None,
+            };
+            if let Some(direction) = direction {
+                self.search_again(direction);
+                return Response::Continue
+            }
+        }
+
+        let status = match self.prompt {
+            Some(ref mut prompt) => prompt.handle_key(key),
+            None                 => return Response::Continue,
+        };
+
+        match status {
+            PromptStatus::Confirmed => {
+                let prompt = self.prompt.take().unwrap();
+                let action = prompt.action.clone();
+                if !self.prompt_history.contains_key(&action) {
+                    self.prompt_history.insert(action.clone(), History::new());
+                }
+                self.prompt_history.get_mut(&action).unwrap().add(prompt.get_text());
+                self.run_prompt_action(action, prompt.get_text());
+            }
+            PromptStatus::Cancelled => {
+                self.prompt = None;
+                self.cancel_search();
+                self.replace_region = None;
+                self.replace_regex = None;
+            }
+            PromptStatus::Edited => {
+                if self.search.is_some() { self.update_search(); }
+            }
+            PromptStatus::Unchanged => {}
+        }
+        Response::Continue
+    }
+
+    /// Answer a question asked before throwing away unsaved changes. `y`
+    /// goes ahead, and any other key leaves everything as it was.
+    fn handle_confirm_key(&mut self, action: PromptAction, key: Key) -> Response {
+        self.prompt = None;
+        if key != Key::from_char('y') && key != Key::from_char('Y') {
+            return Response::Continue
+        }
+
+        match action {
+            PromptAction::ConfirmQuit  => Response::Quit,
+            PromptAction::ConfirmClose => { self.discard_buffer(); Response::Continue }
+            _                          => Response::Continue,
+        }
+    }
+
+    /// Quit, first asking whether to if any open buffer has unsaved changes
+    fn quit(&mut self) -> Response {
+        let modified = self.buffers.iter().filter(|view| view.buffer.borrow().is_modified()).count();
+        if modified == 0 {
+            return Response::Quit
+        }
+
+        let label = if modified == 1 {
+            String::from_str("A buffer has unsaved changes, quit anyway? (y/n) ")
+        } else {
+            format!("{} buffers have unsaved changes, quit anyway? (y/n) ", modified)
+        };
+        self.ask(label.as_slice(), PromptAction::ConfirmQuit);
+        Response::Continue
+    }
+
+    /// Act on the text entered into a prompt
+    fn run_prompt_action(&mut self, action: PromptAction, text: &str) {
+        match action {
+            PromptAction::SaveAs => {
+                if text.is_empty() {
+                    self.message = Some(String::from_str("Not saved, no file name was given"));
+                    return
+                }
+                self.view().buffer.borrow_mut().set_file_path(Path::new(text));
+                self.save_active_buffer();
+            }
+            PromptAction::OpenFile => {
+                if text.is_empty() {
+                    self.message = Some(String::from_str("Nothing opened, no file name was given"));
+                    return
+                }
+                self.open_file(text);
+            }
+            PromptAction::Search => { self.finish_search(text); }
+            PromptAction::GoToLine => {
+                let position = view::parse_position(text, &*self.view().buffer.borrow());
+                match position {
+                    Some((offset, line_num)) => { self.view_mut().move_cursor_to(offset, line_num); }
+                    None => {
+                        self.message = Some(format!("Not a line number: {}", text));
+                    }
+                }
+            }
+            PromptAction::ScrollLines => {
+                match from_str::<int>(text.trim()) {
+                    Some(lines) => { self.view_mut().scroll_by(lines); }
+                    None => {
+                        self.message = Some(format!("Not a number of lines: {}", text));
+                    }
+                }
+            }
+
+            PromptAction::ReplaceLines => {
+                let region = replace::parse_line_range(text, &*self.view().buffer.borrow());
+                match region {
+                    Some(region) => {
+                        self.replace_region = Some(region);
+                        self.ask("Replace regex: ", PromptAction::ReplacePattern);
+                    }
+                    None => {
+                        self.message = Some(format!("Not a range of lines in the buffer: {}", text));
+                    }
+                }
+            }
+            PromptAction::ReplacePattern => {
+                if text.is_empty() {
+                    self.replace_region = None;
+                    self.message = Some(String::from_str("Nothing replaced, no regex was given"));
+                    return
+                }
+                match Regex::new(text) {
+                    Ok(regex) => {
+                        self.replace_regex = Some(regex);
+                        self.ask(format!("Replace {} with: ", text).as_slice(), PromptAction::ReplaceWith);
+                    }
+                    Err(error) => {
+                        self.replace_region = None;
+                        self.message = Some(format!("Invalid regex: {}", error));
+                    }
+                }
+            }
+            PromptAction::ReplaceWith => {
+                let (regex, region) = match (self.replace_regex.take(), self.replace_region.take()) {
+                    (Some(regex), Some(region)) => (regex, region),
+                    _                           => return,
+                };
+                // the selection would no longer line up with the text once
+                // it has been changed
+                self.end_selection();
+
+                self.replace = Some(Replace::new(regex, text, region));
+                self.next_replace();
+            }
+            PromptAction::ConfirmReplace |
+            PromptAction::ConfirmQuit |
+            PromptAction::ConfirmClose => {}
+        }
+    }
 
-        for line in lines.iter() {
-            let mut data = line.borrow().data.clone();
-            data.push('\n' as u8);
-            let result = file.write(data.as_slice());
+    /// Start replacing the matches for a regex in the selection, or in the
+    /// whole buffer if nothing is selected
+    fn start_replace(&mut self) {
+        let region = {
+            let view = self.view();
+            let buffer = view.buffer.borrow();
+            match view.get_selection() {
+                // a selection ending with a line break stops at the end of
+                // the line before, as matches don't span lines
+                Some((start, (0, end_line))) if end_line > 0 => {
+                    (start, (buffer.get_line(end_line - 1).len(), end_line - 1))
+                }
+                Some(selection) => selection,
+                None            => replace::whole_buffer(&*buffer),
+            }
+        };
+        self.replace_region = Some(region);
+        self.ask("Replace regex: ", PromptAction::ReplacePattern);
+    }
 
-            if result.is_err() {
-                // TODO(greg): figure out what to do here.
-                panic!("Something went wrong while writing the file");
+    /// Move to the next match of the replace and ask whether to replace it,
+    /// or finish the replace if there are no more
+    fn next_replace(&mut self) {
+        let found = match self.replace {
+            Some(ref replace) => replace.find_next(&*self.view().buffer.borrow()),
+            None              => return,
+        };
+
+        match found {
+            Some(found) => {
+                let ((offset, line_num), _) = found.region;
+                self.view_mut().move_cursor_to(offset, line_num);
+                self.view_mut().set_highlight(Some(found.region));
+
+                let label = format!("Replace with '{}'? (y/n/a/q) ", found.replacement);
+                self.ask(label.as_slice(), PromptAction::ConfirmReplace);
             }
+            None => self.finish_replace(),
         }
     }
 
+    /// Act on the answer to whether the match picked out should be replaced:
+    /// `y` or Space replaces it, `n` skips it, `a` or `!` replaces it along
+    /// with every match after it, and `q`, Enter or Esc stops.
+    fn handle_replace_key(&mut self, key: Key) {
+        let mut replace = match self.replace.take() {
+            Some(replace) => replace,
+            None          => return,
+        };
+        let handle = self.view().buffer.clone();
+
+        let answer = key.get_printable_char();
+        let done = match answer {
+            Some('y') | Some(' ') | Some('n') => {
+                let found = replace.find_next(&*handle.borrow());
+                match found {
+                    Some(ref found) if answer == Some('n') => replace.skip(&*handle.borrow(), found),
+                    Some(ref found) => { replace.replace(&mut *handle.borrow_mut(), found); }
+                    None => {}
+                }
+                false
+            }
+            Some('a') | Some('!') => {
+                replace.replace_all(&mut *handle.borrow_mut());
+                true
+            }
+            Some('q') => true,
+            _ if key == keyboard::ENTER || key == keyboard::ESC => true,
+            _ => {
+                // keep asking until there is an answer
+                self.replace = Some(replace);
+                return
+            }
+        };
+
+        self.replace = Some(replace);
+        if done {
+            self.finish_replace();
+        } else {
+            self.next_replace();
+        }
+    }
+
+    /// Stop replacing, and show how many matches were replaced
+    fn finish_replace(&mut self) {
+        self.prompt = None;
+        self.view_mut().set_highlight(None);
+        if let Some(replace) = self.replace.take() {
+            let noun = if replace.count == 1 { "match" } else { "matches" };
+            self.message = Some(format!("Replaced {} {}", replace.count, noun));
+        }
+    }
+
+    /// Start a search from the cursor, which moves to the nearest match as
+    /// the text to search for is typed
+    fn start_search(&mut self, direction: SearchDirection) {
+        let origin = self.view().clone();
+        self.ask(search_label(&direction, true), PromptAction::Search);
+        self.search = Some(Search { direction: direction, origin: origin });
+    }
+
+    /// Move to the nearest match for the text in the prompt from where the
+    /// search started, picking it out on screen
+    fn update_search(&mut self) {
+        let pattern = match self.prompt {
+            Some(ref prompt) => String::from_str(prompt.get_text()),
+            None             => return,
+        };
+        let (from, direction) = match self.search {
+            Some(ref search) => (search.origin.cursor.get_position(), search.direction.clone()),
+            None             => return,
+        };
+
+        if pattern.is_empty() {
+            // nothing to search for, so go back to where the search started
+            let (offset, line_num) = from;
+            self.view_mut().move_cursor_to(offset, line_num);
+            self.view_mut().set_highlight(None);
+            if let Some(ref mut prompt) = self.prompt {
+                prompt.set_label(search_label(&direction, true));
+            }
+            return
+        }
+
+        let found = self.find(pattern.as_slice(), from, direction.clone());
+        self.show_search_result(found, &direction);
+    }
+
+    /// Move on to the next match in `direction` for the text in the prompt,
+    /// which also becomes the direction of the search
+    fn search_again(&mut self, direction: SearchDirection) {
+        let pattern = match self.prompt {
+            Some(ref prompt) => String::from_str(prompt.get_text()),
+            None             => return,
+        };
+        if let Some(ref mut search) = self.search {
+            search.direction = direction.clone();
+        }
+
+        let from = self.search_start(&direction);
+        let found = self.find(pattern.as_slice(), from, direction.clone());
+        self.show_search_result(found, &direction);
+    }
+
+    /// Pick out the match found while searching, and say in the prompt
+    /// whether there was one
+    fn show_search_result(&mut self, found: Option<Region>, direction: &SearchDirection) {
+        self.view_mut().set_highlight(found);
+        if let Some(ref mut prompt) = self.prompt {
+            prompt.set_label(search_label(direction, found.is_some()));
+        }
+    }
+
+    /// Stop searching, leaving the cursor at the match it moved to
+    fn finish_search(&mut self, text: &str) {
+        if let Some(search) = self.search.take() {
+            self.view_mut().set_highlight(None);
+            if !text.is_empty() {
+                self.last_search = Some((String::from_str(text), search.direction));
+            }
+        }
+    }
+
+    /// Stop searching, putting the cursor back where the search started
+    fn cancel_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            *self.view_mut() = search.origin;
+        }
+    }
+
+    /// Search for the text of the last search again. The search goes the
+    /// same way as before, or the other way if `reverse` is set.
+    fn repeat_search(&mut self, reverse: bool) {
+        let (pattern, direction) = match self.last_search {
+            Some((ref pattern, ref direction)) => (pattern.clone(), direction.clone()),
+            None => {
+                self.message = Some(String::from_str("No previous search"));
+                return
+            }
+        };
+        let direction = match (direction, reverse) {
+            (direction, false)              => direction,
+            (SearchDirection::Forward, _)   => SearchDirection::Backward,
+            (SearchDirection::Backward, _)  => SearchDirection::Forward,
+        };
+
+        let from = self.search_start(&direction);
+        match self.find(pattern.as_slice(), from, direction.clone()) {
+            None => {
+                self.message = Some(format!("Not found: {}", pattern));
+            }
+            Some((start, _)) => {
+                let (start_offset, start_line) = start;
+                let (from_offset, from_line) = from;
+                let wrapped = match direction {
+                    SearchDirection::Forward  => (start_line, start_offset) < (from_line, from_offset),
+                    SearchDirection::Backward => (start_line, start_offset) >= (from_line, from_offset),
+                };
+                if wrapped {
+                    self.message = Some(String::from_str("Search wrapped around"));
+                }
+            }
+        }
+    }
+
+    /// Get the position to look for the next match from, which is just past
+    /// the cursor when searching forward so that a match under the cursor
+    /// is skipped
+    fn search_start(&self, direction: &SearchDirection) -> (uint, uint) {
+        let (offset, line_num) = self.view().cursor.get_position();
+        match *direction {
+            SearchDirection::Forward  => (offset + 1, line_num),
+            SearchDirection::Backward => (offset, line_num),
+        }
+    }
+
+    /// Find the nearest match for `pattern` in the active buffer and move the
+    /// cursor to the start of it
+    fn find(&mut self, pattern: &str, from: (uint, uint), direction: SearchDirection) -> Option<Region> {
+        let ignore_case = self.options.case_matching.ignores_case(pattern);
+        let found = search::find(&*self.view().buffer.borrow(), pattern, from, direction, ignore_case);
+
+        if let Some((start, _)) = found {
+            let (offset, line_num) = start;
+            self.view_mut().move_cursor_to(offset, line_num);
+        }
+        found
+    }
+
+    /// Show the file at `path` in the active pane, switching to its buffer if
+    /// it is already open. A file which doesn't exist yet is created when it
+    /// is first saved.
+    pub fn open_file(&mut self, path: &str) {
+        let file_path = Path::new(path);
+        let open = self.buffers.iter().position(|view| {
+            view.buffer.borrow().file_path.as_ref() == Some(&file_path)
+        });
+
+        match open {
+            Some(index) => self.show_buffer(index),
+            None        => self.open_buffer(Input::Filename(Some(String::from_str(path)))),
+        }
+    }
+
+    /// Save the active buffer, showing the outcome in the status bar.
+    ///
+    /// If the buffer doesn't have a file path yet, a prompt asks for one.
+    pub fn save_active_buffer(&mut self) {
+        let handle = self.view().buffer.clone();
+        let mut buffer = handle.borrow_mut();
+
+        if buffer.file_path.is_none() {
+            self.ask("Save as: ", PromptAction::SaveAs);
+            return
+        }
+
+        self.message = Some(match buffer.save() {
+            Ok(())     => format!("Saved {}", buffer.get_name()),
+            Err(error) => format!("Could not save {}: {}", buffer.get_name(), error),
+        });
+    }
+
     pub fn draw(&mut self) {
-        self.view.draw();
-        self.view.draw_status();
-        self.view.draw_cursor();
+        let width = self.uibuf.get_width();
+        let height = self.uibuf.get_height();
+        let mut panes = Vec::new();
+        let mut separators = Vec::new();
+        self.layout.arrange(Rect::new(0, 0, width, height), &mut panes, &mut separators);
+
+        self.uibuf.fill(' ');
+
+        // keys typed towards a longer binding are shown until it's finished
+        let message = if self.pending_keys.is_empty() {
+            self.message.clone()
+        } else {
+            Some(format!("{}-", self.pending_key_names()))
+        };
+
+        for &(pane, ref rect) in panes.iter() {
+            // the terminal may be too small to show every pane
+            if rect.width == 0 || rect.height == 0 { continue }
+
+            let view = &mut self.panes[pane];
+            view.resize(rect.width, rect.height);
+            view.clamp_cursor();
+            view.draw(&mut self.uibuf, rect.x, rect.y);
+
+            let focused = pane == self.active_pane;
+            match (&self.prompt, &message) {
+                (&Some(ref prompt), _) if focused => {
+                    let text = prompt.get_display_text();
+                    view.draw_message(&mut self.uibuf, rect.x, rect.y, text.as_slice())
+                }
+                (_, &Some(ref message)) if focused => {
+                    view.draw_message(&mut self.uibuf, rect.x, rect.y, message.as_slice())
+                }
+                _ => view.draw_status(&mut self.uibuf, rect.x, rect.y, focused, self.mode.name()),
+            }
+        }
+
+        for separator in separators.iter() {
+            for row in range(separator.y, separator.y + separator.height) {
+                self.uibuf.update_cell_content(separator.x, row, '│');
+            }
+        }
+
+        self.uibuf.draw_everything();
+
+        for &(pane, ref rect) in panes.iter() {
+            if pane != self.active_pane || rect.width == 0 || rect.height == 0 { continue }
+
+            match self.prompt {
+                Some(ref prompt) => {
+                    // the prompt takes the place of the status line
+                    let x = cmp::min(prompt.get_cursor_column(), rect.width.saturating_sub(1));
+                    let y = self.panes[pane].get_height();
+                    utils::draw_cursor(rect.x + x, rect.y + y);
+                }
+                None => {
+                    let (x, y) = self.panes[pane].get_cursor_screen_position();
+                    utils::draw_cursor(rect.x + x, rect.y + y);
+                }
+            }
+        }
     }
 
     pub fn start(&mut self) {
+        if self.options.mouse {
+            terminal::enable_mouse();
+        }
         self.running = true;
         self.event_loop();
         self.main_loop();
@@ -83,12 +929,19 @@ impl<'e> Editor<'e> {
 
     fn main_loop(&mut self) {
         while self.running {
-            self.view.clear();
             self.draw();
             rustbox::present();
-            if let rustbox::Event::KeyEvent(_, key, ch) = self.events.recv() {
-                if let Response::Quit = self.handle_key_event(key, ch) {
-                    self.running = false;
+            match self.events.recv() {
+                Event::Key(key, ch) => {
+                    if let Response::Quit = self.handle_key_event(key, ch) {
+                        self.running = false;
+                    }
+                }
+                Event::Mouse(button, x, y) => {
+                    self.handle_mouse_event(button, x, y);
+                }
+                Event::Resize(..) => {
+                    self.resize();
                 }
             }
         }
@@ -100,42 +953,418 @@ impl<'e> Editor<'e> {
 
         spawn(proc() {
             loop {
-                sender.send(rustbox::poll_event());
+                if let Some(event) = terminal::poll_event() {
+                    sender.send(event);
+                }
             }
         });
     }
 
-    fn handle_system_event(&mut self, k: Option<Key>) -> EventStatus {
-        use super::keyboard;
+    /// Act on a mouse button pressed or released, or the wheel turned, at
+    /// (x, y) on the screen.
+    ///
+    /// Clicking in a pane focuses it and moves the cursor to the text
+    /// clicked on, and dragging selects from there. Terminals which report the
+    /// mouse moving with the button held do so as the button being pressed
+    /// again, so the selection follows the mouse; others only report where
+    /// the button is released, and the selection is made then. The wheel
+    /// scrolls the pane under the mouse without focusing it.
+    fn handle_mouse_event(&mut self, button: u16, x: uint, y: uint) {
+        // the prompt needs an answer first
+        if self.prompt.is_some() { return }
+
+        let button: Key = match num::from_u32(button as u32) {
+            Some(button) => button,
+            None         => return,
+        };
+        let press = self.mouse_press.clone();
+        let dragging = button == keyboard::MOUSE_LEFT || button == keyboard::MOUSE_RELEASE;
+        if button == keyboard::MOUSE_RELEASE {
+            self.mouse_press = None;
+            if press.is_none() { return }
+        }
+
+        let panes = self.arrange_panes();
+        let found = match press {
+            // a drag carries on in the pane it started in, even past its edge
+            Some((pane, _)) if dragging => {
+                panes.into_iter().find(|&(p, _)| p == pane)
+            }
+            _ => panes.into_iter().find(|&(_, ref rect)| rect.contains(x, y)),
+        };
+        let (pane, rect) = match found {
+            Some(found) => found,
+            None        => return,
+        };
+        let column = if x > rect.x { x - rect.x } else { 0 };
+        let row = if y > rect.y { y - rect.y } else { 0 };
+        let row = cmp::min(row, self.panes[pane].get_height().saturating_sub(1));
+
+        if button == keyboard::MOUSE_WHEEL_UP {
+            self.panes[pane].scroll_by(-MOUSE_WHEEL_LINES);
+        } else if button == keyboard::MOUSE_WHEEL_DOWN {
+            self.panes[pane].scroll_by(MOUSE_WHEEL_LINES);
+        } else if dragging {
+            self.message = None;
+            let position = self.panes[pane].get_position_at(column, row);
+
+            match press {
+                None => {
+                    self.end_selection();
+                    self.active_pane = pane;
+                    self.mouse_press = Some((pane, position));
+                }
+                Some((_, pressed_at)) if position != pressed_at && !self.view().has_mark() => {
+                    // the mouse has moved since the button was pressed, so
+                    // it selects from where it was pressed
+                    let (offset, line_num) = pressed_at;
+                    self.view_mut().move_cursor_to(offset, line_num);
+                    self.view_mut().set_mark();
+                }
+                Some(_) => {}
+            }
+            let (offset, line_num) = position;
+            self.view_mut().move_cursor_to(offset, line_num);
+        }
+    }
+
+    /// Get the area of the screen each pane on it takes up
+    fn arrange_panes(&self) -> Vec<(uint, Rect)> {
+        let mut panes = Vec::new();
+        let mut separators = Vec::new();
+        let area = Rect::new(0, 0, self.uibuf.get_width(), self.uibuf.get_height());
+        self.layout.arrange(area, &mut panes, &mut separators);
+        panes
+    }
 
+    /// Look up the Command bound to the keys typed so far in the active
+    /// mode, and run it once a whole binding has been typed.
+    ///
+    /// Esc cancels a partly typed binding.
+    fn handle_system_event(&mut self, k: Option<Key>) -> EventStatus {
         let key = match k {
             Some(k) => k,
             None => return EventStatus::NotHandled
         };
 
-        match key {
-            keyboard::UP        => { self.view.move_cursor(Direction::Up); }
-            keyboard::DOWN      => { self.view.move_cursor(Direction::Down); }
-            keyboard::LEFT      => { self.view.move_cursor(Direction::Left); }
-            keyboard::RIGHT     => { self.view.move_cursor(Direction::Right); }
-            keyboard::ENTER     => { self.view.insert_line(); }
+        if key == keyboard::ESC && !self.pending_keys.is_empty() {
+            self.pending_keys.clear();
+            return EventStatus::Handled(Response::Continue)
+        }
+
+        self.pending_keys.push(key);
+        match self.keymap.lookup(&self.mode, self.pending_keys.as_slice()) {
+            Lookup::Command(command) => {
+                self.pending_keys.clear();
+                EventStatus::Handled(self.run_command(command))
+            }
+            Lookup::Prefix => EventStatus::Handled(Response::Continue),
+            Lookup::Unbound => {
+                if self.pending_keys.len() > 1 {
+                    self.message = Some(format!("{} is not bound", self.pending_key_names()));
+                }
+                self.pending_keys.clear();
+                EventStatus::NotHandled
+            }
+        }
+    }
+
+    /// Get the names of the keys typed towards a longer binding
+    fn pending_key_names(&self) -> String {
+        let names: Vec<String> = self.pending_keys.iter().map(|key| key.name()).collect();
+        names.connect(" ")
+    }
+
+    fn run_command(&mut self, command: Command) -> Response {
+        // a paste can only be swapped for an earlier one straight after it
+        let last_paste = self.last_paste.take();
 
-            // Tab inserts 4 spaces, rather than a \t
-            keyboard::TAB       => { self.view.insert_tab(); }
+        match command {
+            Command::MoveCursor(direction) => { self.view_mut().move_cursor(direction); }
+            Command::Move(motion)          => { self.view_mut().move_cursor_by(motion); }
+            Command::ScrollPage(direction) => { self.view_mut().scroll_page(direction); }
+            Command::ScrollLine(direction) => { self.scroll_line(direction); }
+            Command::ScrollLines           => { self.ask("Scroll by lines: ", PromptAction::ScrollLines); }
+            Command::Recenter              => { self.view_mut().recenter(); }
+            Command::GoToLine              => { self.ask("Go to line: ", PromptAction::GoToLine); }
+            Command::InsertChar(ch)        => { self.view_mut().insert_char(ch); }
+            Command::InsertLine            => { self.view_mut().insert_line(); }
+            Command::DeleteLine            => { self.view_mut().delete_line(); }
+
+            // Tab and the delete keys act on the selection when there is one
+            Command::InsertTab if self.has_selection() => {
+                self.view_mut().indent_lines();
+                self.end_selection();
+            }
+            Command::DeleteChar(_) if self.has_selection() => {
+                self.view_mut().delete_selection();
+                self.end_selection();
+            }
+            Command::InsertTab             => { self.view_mut().insert_tab(); }
+            Command::DeleteChar(direction) => { self.view_mut().delete_char(direction); }
+
+            Command::DeleteSelection => {
+                self.view_mut().delete_selection();
+                self.end_selection();
+            }
+            Command::Indent => {
+                self.view_mut().indent_lines();
+                self.end_selection();
+            }
+            Command::Dedent => {
+                self.view_mut().dedent_lines();
+                self.end_selection();
+            }
+            Command::SetMark => { self.toggle_mark(); }
+
+            Command::Cut           => { self.cut(); }
+            Command::Copy          => { self.copy(); }
+            Command::Paste         => { self.paste(); }
+            Command::PastePrevious => { self.paste_previous(last_paste); }
+
+            Command::Append => {
+                self.view_mut().move_cursor(Direction::Right);
+                self.set_mode(Mode::Insert);
+            }
+            Command::OpenLineBelow => {
+                self.view_mut().open_line_below();
+                self.set_mode(Mode::Insert);
+            }
 
-            keyboard::BACKSPACE => { self.view.delete_char(Direction::Left); }
-            keyboard::DELETE    => { self.view.delete_char(Direction::Right); }
-            keyboard::CTRL_S     => { self.save_active_buffer(); }
-            keyboard::CTRL_Q     => { return EventStatus::Handled(Response::Quit) }
-            keyboard::CTRL_R     => { self.view.resize(); }
+            Command::Undo      => { self.view_mut().undo(); }
+            Command::Redo      => { self.view_mut().redo(); }
+            Command::Save      => { self.save_active_buffer(); }
+            Command::Quit      => { return self.quit() }
+            Command::ForceQuit => { return Response::Quit }
+            Command::Resize    => { self.resize(); }
+
+            Command::OpenBuffer  => { self.open_buffer(Input::Filename(None)); }
+            Command::OpenFile    => { self.ask("Open file: ", PromptAction::OpenFile); }
+            Command::NextBuffer  => { self.next_buffer(); }
+            Command::PrevBuffer  => { self.prev_buffer(); }
+            Command::CloseBuffer => { self.close_buffer(); }
+            Command::ListBuffers => { self.list_buffers(); }
+
+            Command::SplitPane(orientation) => { self.split_pane(orientation); }
+            Command::FocusNextPane          => { self.focus_next_pane(); }
+            Command::ClosePane              => { self.close_pane(); }
+
+            Command::Search(direction) => { self.start_search(direction); }
+            Command::SearchNext        => { self.repeat_search(false); }
+            Command::SearchPrev        => { self.repeat_search(true); }
+            Command::Replace           => { self.start_replace(); }
+            Command::ReplaceLines      => { self.ask("Replace in lines: ", PromptAction::ReplaceLines); }
+
+            Command::ToggleWrap       => { self.view_mut().toggle_wrap(); }
+            Command::CycleLineNumbers => { self.cycle_line_numbers(); }
+            Command::CycleLineEnding  => { self.cycle_line_ending(); }
+
+            Command::SwitchMode(mode) => { self.set_mode(mode); }
+        }
+        Response::Continue
+    }
+
+    /// Switch to another editing mode.
+    ///
+    /// Visual mode selects from the cursor position it was started at.
+    fn set_mode(&mut self, mode: Mode) {
+        if mode == Mode::Visual {
+            self.view_mut().set_visual_mark();
+        } else {
+            self.view_mut().clear_mark();
+        }
+        self.mode = mode;
+    }
+
+    /// Scroll the active View up or down by a line
+    fn scroll_line(&mut self, direction: Direction) {
+        match direction {
+            Direction::Up   => { self.view_mut().scroll_by(-1); }
+            Direction::Down => { self.view_mut().scroll_by(1); }
+            _               => {}
+        }
+    }
+
+    /// Cut the selected text, or the cursor line if nothing is selected,
+    /// into the kill ring
+    fn cut(&mut self) {
+        let selected = self.view().get_selected_text();
+        match selected {
+            Some(text) => {
+                self.kill(text, false);
+                self.view_mut().delete_selection();
+                self.end_selection();
+            }
+            None => {
+                let text = self.view().get_cursor_line_text();
+                self.kill(text, true);
+                self.view_mut().delete_line();
+            }
+        }
+    }
+
+    /// Copy the selected text, or the cursor line if nothing is selected,
+    /// into the kill ring
+    fn copy(&mut self) {
+        let (text, linewise) = match self.view().get_selected_text() {
+            Some(text) => (text, false),
+            None       => (self.view().get_cursor_line_text(), true),
+        };
+        self.end_selection();
+        self.message = Some(String::from_str("Copied"));
+        self.kill(text, linewise);
+    }
 
-            // TODO(greg): move these keys to event handlers of each mode
-            // This block is for matching keys which will insert a char to the buffer
-            _ => { self.view.insert_char(key.get_char().unwrap()) }
+    /// Add text which has been cut or copied to the kill ring, and share it
+    /// with the system clipboard
+    fn kill(&mut self, text: String, linewise: bool) {
+        if let Err(error) = self.clipboard.copy(text.as_slice()) {
+            self.message = Some(format!("Could not copy to the clipboard: {}", error));
         }
-        // event is handled and we want to keep the editor running
-        EventStatus::Handled(Response::Continue)
+        self.kill_ring.push(text, linewise);
     }
 
+    /// Paste the newest text in the kill ring at the cursor.
+    ///
+    /// Text put on the system clipboard from outside iota is added to the
+    /// kill ring first, so that it is the text pasted. Text which was copied
+    /// from iota is left as it is in the kill ring, as it may be whole lines.
+    fn paste(&mut self) {
+        match self.clipboard.paste() {
+            Some(Ok(text)) => {
+                let is_new = self.kill_ring.get(0).map_or(true, |kill| {
+                    !clipboard::same_text(kill.text.as_slice(), text.as_slice())
+                });
+                if is_new {
+                    self.kill_ring.push(text, false);
+                }
+            }
+            Some(Err(error)) => {
+                self.message = Some(format!("Could not paste from the clipboard: {}", error));
+            }
+            None => {}
+        }
+
+        let kill = match self.kill_ring.get(0) {
+            Some(kill) => kill.clone(),
+            None       => {
+                self.message = Some(String::from_str("Nothing to paste"));
+                return
+            }
+        };
+        let region = self.view_mut().paste(kill.text.as_slice(), kill.linewise);
+        self.last_paste = Some((self.active_pane, region, 0));
+    }
+
+    /// Swap the text put in by the paste just made for the text cut or
+    /// copied before it, going round to the newest again after the oldest
+    fn paste_previous(&mut self, last_paste: Option<(uint, Region, uint)>) {
+        let (region, index) = match last_paste {
+            Some((pane, region, index)) if pane == self.active_pane => (region, index),
+            _ => {
+                self.message = Some(String::from_str("Can only paste an earlier entry straight after pasting"));
+                return
+            }
+        };
+
+        let index = (index + 1) % self.kill_ring.len();
+        let kill = self.kill_ring.get(index).unwrap().clone();
+        let region = self.view_mut().replace_paste(region, kill.text.as_slice(), kill.linewise);
+        self.last_paste = Some((self.active_pane, region, index));
+    }
+
+    fn has_selection(&self) -> bool {
+        self.view().get_selection().is_some()
+    }
+
+    /// Clear the selection once it has been acted on, going back to normal
+    /// mode if it was made in visual mode
+    fn end_selection(&mut self) {
+        if self.mode == Mode::Visual {
+            self.set_mode(Mode::Normal);
+        } else {
+            self.view_mut().clear_mark();
+        }
+    }
+
+    /// Start a selection at the cursor, or clear the mark if there is one
+    /// already
+    fn toggle_mark(&mut self) {
+        if self.view().has_mark() {
+            self.end_selection();
+            self.message = Some(String::from_str("Mark cleared"));
+        } else {
+            self.view_mut().set_mark();
+            self.message = Some(String::from_str("Mark set"));
+        }
+    }
+
+}
+
+
+/// Get the label for the search prompt, which says which way the search goes
+/// and whether the text typed so far was found
+fn search_label(direction: &SearchDirection, found: bool) -> &'static str {
+    match (direction, found) {
+        (&SearchDirection::Forward, true)   => "Search: ",
+        (&SearchDirection::Backward, true)  => "Search backward: ",
+        (&SearchDirection::Forward, false)  => "Failing search: ",
+        (&SearchDirection::Backward, false) => "Failing search backward: ",
+    }
 }
 
+
+#[cfg(test)]
+mod tests {
+
+    use clipboard::SystemClipboard;
+    use editor::Editor;
+    use input::Input;
+    use options::Options;
+
+    // termbox's codes for the left button being pressed and for any button
+    // being released
+    const MOUSE_LEFT: u16 = 0xffff - 22;
+    const MOUSE_RELEASE: u16 = 0xffff - 25;
+
+    fn setup_editor() -> Editor {
+        let mut editor = Editor::new_with_size(Input::Filename(None), Options::new(),
+                                               SystemClipboard::new(), 50, 10);
+        for ch in "hello world".chars() {
+            editor.view_mut().insert_char(ch);
+        }
+        editor
+    }
+
+    #[test]
+    fn releasing_the_mouse_elsewhere_selects_from_where_it_was_pressed() {
+        let mut editor = setup_editor();
+        editor.handle_mouse_event(MOUSE_LEFT, 2, 0);
+        editor.handle_mouse_event(MOUSE_RELEASE, 7, 0);
+
+        assert_eq!(editor.view().get_selection(), Some(((2, 0), (8, 0))));
+        assert_eq!(editor.view().cursor.get_position(), (7, 0));
+    }
+
+    #[test]
+    fn dragging_with_the_button_held_selects_as_the_mouse_moves() {
+        let mut editor = setup_editor();
+        editor.handle_mouse_event(MOUSE_LEFT, 6, 0);
+        editor.handle_mouse_event(MOUSE_LEFT, 4, 0);
+        editor.handle_mouse_event(MOUSE_LEFT, 1, 0);
+        editor.handle_mouse_event(MOUSE_RELEASE, 1, 0);
+
+        assert_eq!(editor.view().get_selection(), Some(((1, 0), (7, 0))));
+        assert_eq!(editor.view().cursor.get_position(), (1, 0));
+    }
+
+    #[test]
+    fn clicking_moves_the_cursor_without_selecting() {
+        let mut editor = setup_editor();
+        editor.handle_mouse_event(MOUSE_LEFT, 4, 0);
+        editor.handle_mouse_event(MOUSE_RELEASE, 4, 0);
+
+        assert_eq!(editor.view().get_selection(), None);
+        assert_eq!(editor.view().cursor.get_position(), (4, 0));
+    }
+}
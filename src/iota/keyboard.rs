@@ -1,25 +1,70 @@
 use std::num::FromPrimitive;
 use std::char;
 
+// termbox reports function keys, arrows and mouse buttons with codes from
+// here upwards
+const FIRST_SPECIAL_KEY: u64 = 65508;
+
+#[deriving(Clone, PartialEq)]
 pub struct Key {
     code: u64
 }
 
+pub const CTRL_SPACE: Key = Key { code: 0 };
+pub const CTRL_A: Key     = Key { code: 1 };
+pub const CTRL_B: Key     = Key { code: 2 };
+pub const CTRL_C: Key     = Key { code: 3 };
+pub const CTRL_E: Key     = Key { code: 5 };
+pub const CTRL_F: Key     = Key { code: 6 };
+pub const CTRL_G: Key     = Key { code: 7 };
+pub const CTRL_K: Key     = Key { code: 11 };
+pub const CTRL_L: Key     = Key { code: 12 };
 pub const TAB: Key       = Key { code: 9 };
 pub const ENTER: Key     = Key { code: 13 };
+pub const CTRL_N: Key     = Key { code: 14 };
+pub const CTRL_O: Key     = Key { code: 15 };
+pub const CTRL_P: Key     = Key { code: 16 };
 pub const CTRL_Q: Key     = Key { code: 17 };
 pub const CTRL_R: Key     = Key { code: 18 };
 pub const CTRL_S: Key     = Key { code: 19 };
-#[allow(dead_code)]
+pub const CTRL_V: Key     = Key { code: 22 };
+pub const CTRL_W: Key     = Key { code: 23 };
+pub const CTRL_X: Key     = Key { code: 24 };
+pub const CTRL_Y: Key     = Key { code: 25 };
+pub const CTRL_Z: Key     = Key { code: 26 };
 pub const ESC: Key       = Key { code: 27 };
 pub const BACKSPACE: Key = Key { code: 127 };
+pub const F11: Key       = Key { code: 65525 };
+pub const F10: Key       = Key { code: 65526 };
+pub const F9: Key        = Key { code: 65527 };
+pub const F8: Key        = Key { code: 65528 };
+pub const F7: Key        = Key { code: 65529 };
+pub const F6: Key        = Key { code: 65530 };
+pub const F5: Key        = Key { code: 65531 };
 pub const RIGHT: Key     = Key { code: 65514 };
 pub const LEFT: Key      = Key { code: 65515 };
 pub const DOWN: Key      = Key { code: 65516 };
 pub const UP: Key        = Key { code: 65517 };
+pub const PGDN: Key      = Key { code: 65518 };
+pub const PGUP: Key      = Key { code: 65519 };
+pub const END: Key       = Key { code: 65520 };
+pub const HOME: Key      = Key { code: 65521 };
 pub const DELETE: Key    = Key { code: 65522 };
 
+// the buttons of mouse events
+pub const MOUSE_WHEEL_DOWN: Key = Key { code: 65508 };
+pub const MOUSE_WHEEL_UP: Key   = Key { code: 65509 };
+pub const MOUSE_RELEASE: Key    = Key { code: 65510 };
+pub const MOUSE_MIDDLE: Key     = Key { code: 65511 };
+pub const MOUSE_RIGHT: Key      = Key { code: 65512 };
+pub const MOUSE_LEFT: Key       = Key { code: 65513 };
+
 impl Key {
+    /// Get the key which types `ch`
+    pub fn from_char(ch: char) -> Key {
+        Key { code: ch as u64 }
+    }
+
     #[inline(always)]
     pub fn code(&self) -> u32 {
         self.code as u32
@@ -28,6 +73,91 @@ impl Key {
     pub fn get_char(&self) -> Option<char> {
         char::from_u32(self.code())
     }
+
+    /// Get the name of the key, written the same way `parse_key` reads it
+    pub fn name(&self) -> String {
+        let name = match self.code {
+            0     => "C-Space",
+            9     => "Tab",
+            13    => "Enter",
+            27    => "Esc",
+            32    => "Space",
+            127   => "Backspace",
+            65523 => "Insert",
+            65522 => "Delete",
+            65521 => "Home",
+            65520 => "End",
+            65519 => "PageUp",
+            65518 => "PageDown",
+            65517 => "Up",
+            65516 => "Down",
+            65515 => "Left",
+            65514 => "Right",
+            1...26 => {
+                let letter = char::from_u32((self.code - 1) as u32 + 'a' as u32).unwrap();
+                return format!("C-{}", letter)
+            }
+            65524...65535 => return format!("F{}", 65536 - self.code),
+            _ => match self.get_char() {
+                Some(ch) => return ch.to_string(),
+                None     => return format!("<{}>", self.code),
+            }
+        };
+        String::from_str(name)
+    }
+
+    /// Get the character typed, unless this is a control key or one of the
+    /// special keys such as the arrows
+    pub fn get_printable_char(&self) -> Option<char> {
+        if self.code >= FIRST_SPECIAL_KEY { return None }
+        match self.get_char() {
+            Some(ch) if !ch.is_control() => Some(ch),
+            _                            => None,
+        }
+    }
+}
+
+/// Parse the name of a key, as written in the keymap file.
+///
+/// A key is either a single char, `C-` followed by a letter or `Space` for a
+/// control key, or one of the names `Tab`, `Enter`, `Esc`, `Backspace`,
+/// `Delete`, `Insert`, `Home`, `End`, `PageUp`, `PageDown`, `Up`, `Down`,
+/// `Left`, `Right`, `Space` and `F1` to `F12`.
+pub fn parse_key(name: &str) -> Option<Key> {
+    let code = match name {
+        "C-Space"   => 0,
+        "Tab"       => 9,
+        "Enter"     => 13,
+        "Esc"       => 27,
+        "Space"     => 32,
+        "Backspace" => 127,
+        "Insert"    => 65523,
+        "Delete"    => 65522,
+        "Home"      => 65521,
+        "End"       => 65520,
+        "PageUp"    => 65519,
+        "PageDown"  => 65518,
+        "Up"        => 65517,
+        "Down"      => 65516,
+        "Left"      => 65515,
+        "Right"     => 65514,
+        _ if name.starts_with("C-") && name.char_len() == 3 => {
+            match name.char_at(2).to_lowercase() {
+                ch @ 'a'...'z' => ch as u64 - 'a' as u64 + 1,
+                _              => return None,
+            }
+        }
+        _ if name.starts_with("F") && name.len() > 1 => {
+            // F1 to F12 count down from the top of termbox's key codes
+            match from_str::<u64>(name.slice_from(1)) {
+                Some(n) if n >= 1 && n <= 12 => 65536 - n,
+                _                            => return None,
+            }
+        }
+        _ if name.char_len() == 1 => name.char_at(0) as u64,
+        _ => return None,
+    };
+    Some(Key { code: code })
 }
 
 impl FromPrimitive for Key {
@@ -55,3 +185,35 @@ impl FromPrimitive for Key {
         FromPrimitive::from_u64(n as u64)
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+
+    use keyboard;
+    use keyboard::parse_key;
+
+    #[test]
+    fn parse_key_reads_names_and_chars() {
+        assert_eq!(parse_key("Enter").unwrap().code(), keyboard::ENTER.code());
+        assert_eq!(parse_key("F5").unwrap().code(), keyboard::F5.code());
+        assert_eq!(parse_key("C-s").unwrap().code(), keyboard::CTRL_S.code());
+        assert_eq!(parse_key("x").unwrap().code(), 'x' as u32);
+        assert_eq!(parse_key("漢").unwrap().code(), '漢' as u32);
+    }
+
+    #[test]
+    fn names_can_be_parsed_back() {
+        for name in ["Tab", "Esc", "C-x", "C-Space", "F12", "PageDown", "x", "=", "漢"].iter() {
+            assert_eq!(parse_key(*name).unwrap().name().as_slice(), *name);
+        }
+    }
+
+    #[test]
+    fn parse_key_rejects_unknown_names() {
+        assert!(parse_key("C-1").is_none());
+        assert!(parse_key("F13").is_none());
+        assert!(parse_key("Foo").is_none());
+        assert!(parse_key("").is_none());
+    }
+}
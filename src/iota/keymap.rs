@@ -0,0 +1,302 @@
+use std::collections::{HashMap, HashSet};
+use std::io::File;
+use std::io::fs::PathExtensions;
+use std::os;
+
+use command::Command;
+use keyboard;
+use keyboard::Key;
+use modes;
+use modes::Mode;
+
+/// What a sequence of key presses is bound to
+#[deriving(Clone, PartialEq, Show)]
+pub enum Lookup {
+    /// The keys make up a whole binding, which runs the Command
+    Command(Command),
+    /// The keys are the start of at least one longer binding
+    Prefix,
+    /// No binding starts with the keys
+    Unbound,
+}
+
+
+/// Maps sequences of key presses to the Commands they run.
+///
+/// Each mode has its own bindings, along with a set of global bindings used
+/// for keys which the active mode doesn't bind. The keymap starts out with the
+/// default bindings, which can be changed by a keymap file such as:
+///
+/// ```text
+/// # bindings before any section are global
+/// C-s = save
+/// C-x C-c = quit
+///
+/// [normal]
+/// w = move-right
+/// ```
+pub struct Keymap {
+    // the mode is None for global bindings
+    bindings: HashMap<(Option<Mode>, Vec<u32>), Command>,
+    // every sequence which is the start of a longer binding
+    prefixes: HashSet<(Option<Mode>, Vec<u32>)>,
+}
+
+impl Keymap {
+    /// Create a keymap with the default bindings
+    pub fn new() -> Keymap {
+        let mut keymap = Keymap {
+            bindings: HashMap::new(),
+            prefixes: HashSet::new(),
+        };
+
+        for (keys, command) in modes::global_bindings().into_iter() {
+            keymap.bind(None, keys.as_slice(), command);
+        }
+        for mode in [Mode::Normal, Mode::Insert, Mode::Visual].iter() {
+            for (keys, command) in mode.bindings().into_iter() {
+                keymap.bind(Some(mode.clone()), keys.as_slice(), command);
+            }
+        }
+        keymap
+    }
+
+    /// Create a keymap with the default bindings, changed by the user's keymap
+    /// file if there is one.
+    ///
+    /// Returns the keymap along with any errors found in the file.
+    pub fn from_user_config() -> (Keymap, Vec<String>) {
+        let mut keymap = Keymap::new();
+        let path = match config_path() {
+            Some(ref path) if path.exists() => path.clone(),
+            _                               => return (keymap, Vec::new()),
+        };
+
+        let errors = match File::open(&path).read_to_string() {
+            Ok(text)   => keymap.load(text.as_slice()),
+            Err(error) => vec![format!("could not read {}: {}", path.display(), error)],
+        };
+        (keymap, errors)
+    }
+
+    /// Bind the sequence `keys` to `command`, in `mode` or globally if `mode`
+    /// is None
+    pub fn bind(&mut self, mode: Option<Mode>, keys: &[Key], command: Command) {
+        let codes: Vec<u32> = keys.iter().map(|key| key.code()).collect();
+        for length in range(1, codes.len()) {
+            self.prefixes.insert((mode.clone(), codes.slice_to(length).to_vec()));
+        }
+        self.bindings.insert((mode, codes), command);
+    }
+
+    /// Find what the sequence `keys` is bound to in `mode`.
+    ///
+    /// The bindings for the mode are checked before the global ones. A
+    /// sequence which is both a whole binding and the start of a longer one
+    /// runs its Command straight away.
+    pub fn lookup(&self, mode: &Mode, keys: &[Key]) -> Lookup {
+        let codes: Vec<u32> = keys.iter().map(|key| key.code()).collect();
+
+        for section in [Some(mode.clone()), None].iter() {
+            let entry = (section.clone(), codes.clone());
+            if let Some(command) = self.bindings.get(&entry) {
+                return Lookup::Command(command.clone())
+            }
+            if self.prefixes.contains(&entry) {
+                return Lookup::Prefix
+            }
+        }
+
+        // a single key may still do something in this mode
+        if keys.len() == 1 {
+            if let Some(command) = mode.unbound_key(&keys[0]) {
+                return Lookup::Command(command)
+            }
+        }
+        Lookup::Unbound
+    }
+
+    /// Add the bindings in the text of a keymap file, replacing any existing
+    /// bindings for the same keys.
+    ///
+    /// Every line is either a `[section]` naming a mode, or `keys = command`
+    /// where `keys` is one or more keys separated by spaces. Blank lines and
+    /// lines starting with '#' are skipped. Returns a description of each
+    /// line which couldn't be understood.
+    pub fn load(&mut self, text: &str) -> Vec<String> {
+        let mut errors = Vec::new();
+        let mut mode = None;
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            let line_num = index + 1;
+            if line.is_empty() || line.starts_with("#") { continue }
+
+            if line.starts_with("[") && line.ends_with("]") {
+                let name = line.slice(1, line.len() - 1).trim();
+                match name {
+                    "global" => mode = None,
+                    _ => match Mode::from_name(name) {
+                        Some(m) => mode = Some(m),
+                        None    => errors.push(format!("line {}: unknown mode '{}'", line_num, name)),
+                    }
+                }
+                continue
+            }
+
+            // split on the last '=', so that '=' can be bound too
+            let (key_names, command_name) = match line.rfind('=') {
+                Some(i) => (line.slice_to(i).trim(), line.slice_from(i + 1).trim()),
+                None    => {
+                    errors.push(format!("line {}: expected 'keys = command'", line_num));
+                    continue
+                }
+            };
+
+            let keys = match parse_keys(key_names) {
+                Ok(keys)   => keys,
+                Err(error) => {
+                    errors.push(format!("line {}: {}", line_num, error));
+                    continue
+                }
+            };
+
+            match Command::from_name(command_name) {
+                Some(command) => self.bind(mode.clone(), keys.as_slice(), command),
+                None => errors.push(format!("line {}: unknown command '{}'", line_num, command_name)),
+            }
+        }
+
+        errors
+    }
+}
+
+
+/// Parse a sequence of key names separated by spaces
+fn parse_keys(names: &str) -> Result<Vec<Key>, String> {
+    let mut keys = Vec::new();
+    for name in names.split(' ').filter(|name| !name.is_empty()) {
+        match keyboard::parse_key(name) {
+            Some(key) => keys.push(key),
+            None      => return Err(format!("unknown key '{}'", name)),
+        }
+    }
+
+    if keys.is_empty() {
+        return Err(String::from_str("expected 'keys = command'"))
+    }
+    Ok(keys)
+}
+
+
+/// Get the path of the user's keymap file, which is `iota/keymap` in
+/// $XDG_CONFIG_HOME, or in ~/.config if that isn't set
+pub fn config_path() -> Option<Path> {
+    let config_dir = match os::getenv("XDG_CONFIG_HOME") {
+        Some(ref dir) if !dir.is_empty() => Path::new(dir.as_slice()),
+        _ => match os::homedir() {
+            Some(home) => home.join(".config"),
+            None       => return None,
+        },
+    };
+    Some(config_dir.join("iota").join("keymap"))
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use command::Command;
+    use cursor::Direction;
+    use keyboard;
+    use keyboard::Key;
+    use keymap::{Keymap, Lookup};
+    use layout::Orientation;
+    use modes::Mode;
+
+    fn ch(c: char) -> Key {
+        Key::from_char(c)
+    }
+
+    #[test]
+    fn global_bindings_work_in_every_mode() {
+        let keymap = Keymap::new();
+        for mode in [Mode::Normal, Mode::Insert, Mode::Visual].iter() {
+            assert_eq!(keymap.lookup(mode, &[keyboard::CTRL_S]), Lookup::Command(Command::Save));
+            assert_eq!(keymap.lookup(mode, &[keyboard::CTRL_Q]), Lookup::Command(Command::Quit));
+        }
+    }
+
+    #[test]
+    fn printable_keys_depend_on_the_mode() {
+        let keymap = Keymap::new();
+
+        assert_eq!(keymap.lookup(&Mode::Insert, &[ch('x')]), Lookup::Command(Command::InsertChar('x')));
+        assert_eq!(keymap.lookup(&Mode::Normal, &[ch('x')]), Lookup::Command(Command::DeleteChar(Direction::Right)));
+        assert_eq!(keymap.lookup(&Mode::Visual, &[ch('x')]), Lookup::Command(Command::DeleteSelection));
+        assert_eq!(keymap.lookup(&Mode::Normal, &[ch('q')]), Lookup::Unbound);
+    }
+
+    #[test]
+    fn esc_switches_between_modes() {
+        let keymap = Keymap::new();
+
+        assert_eq!(keymap.lookup(&Mode::Insert, &[keyboard::ESC]), Lookup::Command(Command::SwitchMode(Mode::Normal)));
+        assert_eq!(keymap.lookup(&Mode::Visual, &[keyboard::ESC]), Lookup::Command(Command::SwitchMode(Mode::Normal)));
+        assert_eq!(keymap.lookup(&Mode::Normal, &[keyboard::ESC]), Lookup::Unbound);
+    }
+
+    #[test]
+    fn chords_are_looked_up_one_key_at_a_time() {
+        let keymap = Keymap::new();
+
+        assert_eq!(keymap.lookup(&Mode::Insert, &[keyboard::CTRL_X]), Lookup::Prefix);
+        assert_eq!(keymap.lookup(&Mode::Insert, &[keyboard::CTRL_X, keyboard::CTRL_S]), Lookup::Command(Command::Save));
+        assert_eq!(keymap.lookup(&Mode::Normal, &[keyboard::CTRL_X, ch('2')]),
+                   Lookup::Command(Command::SplitPane(Orientation::Horizontal)));
+        assert_eq!(keymap.lookup(&Mode::Insert, &[keyboard::CTRL_X, ch('z')]), Lookup::Unbound);
+
+        assert_eq!(keymap.lookup(&Mode::Normal, &[ch('d')]), Lookup::Prefix);
+        assert_eq!(keymap.lookup(&Mode::Normal, &[ch('d'), ch('d')]), Lookup::Command(Command::DeleteLine));
+        assert_eq!(keymap.lookup(&Mode::Insert, &[ch('d')]), Lookup::Command(Command::InsertChar('d')));
+    }
+
+    #[test]
+    fn load_replaces_bindings_per_mode() {
+        let mut keymap = Keymap::new();
+        let errors = keymap.load("# global\nC-s = quit\n\n[normal]\nq = undo\n= = redo\n");
+
+        assert!(errors.is_empty());
+        assert_eq!(keymap.lookup(&Mode::Insert, &[keyboard::CTRL_S]), Lookup::Command(Command::Quit));
+        assert_eq!(keymap.lookup(&Mode::Normal, &[ch('q')]), Lookup::Command(Command::Undo));
+        assert_eq!(keymap.lookup(&Mode::Normal, &[ch('=')]), Lookup::Command(Command::Redo));
+        assert_eq!(keymap.lookup(&Mode::Insert, &[ch('q')]), Lookup::Command(Command::InsertChar('q')));
+    }
+
+    #[test]
+    fn load_binds_key_sequences() {
+        let mut keymap = Keymap::new();
+        let errors = keymap.load("[normal]\ng g  =  move-up\nC-x Space = save\n");
+
+        assert!(errors.is_empty());
+        assert_eq!(keymap.lookup(&Mode::Normal, &[ch('g')]), Lookup::Prefix);
+        assert_eq!(keymap.lookup(&Mode::Normal, &[ch('g'), ch('g')]), Lookup::Command(Command::MoveCursor(Direction::Up)));
+        assert_eq!(keymap.lookup(&Mode::Normal, &[keyboard::CTRL_X, ch(' ')]), Lookup::Command(Command::Save));
+        assert_eq!(keymap.lookup(&Mode::Insert, &[ch('g')]), Lookup::Command(Command::InsertChar('g')));
+    }
+
+    #[test]
+    fn load_reports_each_bad_line() {
+        let mut keymap = Keymap::new();
+        let errors = keymap.load("C-s = sav\nCtrl-x = save\n[command]\nnonsense\nC-q = quit\n = save\n");
+
+        assert_eq!(errors, vec![
+            String::from_str("line 1: unknown command 'sav'"),
+            String::from_str("line 2: unknown key 'Ctrl-x'"),
+            String::from_str("line 3: unknown mode 'command'"),
+            String::from_str("line 4: expected 'keys = command'"),
+            String::from_str("line 6: expected 'keys = command'"),
+        ]);
+        assert_eq!(keymap.lookup(&Mode::Insert, &[keyboard::CTRL_S]), Lookup::Command(Command::Save));
+    }
+}
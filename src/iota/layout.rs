@@ -0,0 +1,273 @@
+use std::num::Int;
+
+// the smallest area a pane can be split down to, which leaves a row of text
+// above the status line
+const MIN_PANE_WIDTH: uint = 1;
+const MIN_PANE_HEIGHT: uint = 2;
+
+
+/// A rectangular area of the screen.
+#[deriving(Clone, PartialEq, Show)]
+pub struct Rect {
+    pub x: uint,
+    pub y: uint,
+    pub width: uint,
+    pub height: uint,
+}
+
+impl Rect {
+    pub fn new(x: uint, y: uint, width: uint, height: uint) -> Rect {
+        Rect {
+            x: x,
+            y: y,
+            width: width,
+            height: height,
+        }
+    }
+
+    /// Check whether the point (x, y) falls within the Rect
+    pub fn contains(&self, x: uint, y: uint) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+
+    /// Check whether splitting the Rect the given way would leave both halves
+    /// at least the smallest size of a pane
+    pub fn can_split(&self, orientation: &Orientation) -> bool {
+        match *orientation {
+            Orientation::Horizontal => self.height / 2 >= MIN_PANE_HEIGHT,
+            Orientation::Vertical   => self.width.saturating_sub(1) / 2 >= MIN_PANE_WIDTH,
+        }
+    }
+}
+
+
+#[deriving(Clone, PartialEq, Show)]
+pub enum Orientation {
+    /// Panes are stacked one above the other
+    Horizontal,
+    /// Panes are placed side by side
+    Vertical,
+}
+
+
+/// A tree describing how the screen is tiled by panes.
+///
+/// Each leaf holds the index of a pane. A split divides its area in two
+/// between the first and second layouts.
+#[deriving(Clone, PartialEq, Show)]
+pub enum Layout {
+    Pane(uint),
+    Split(Orientation, Box<Layout>, Box<Layout>),
+}
+
+impl Layout {
+    /// Split the area of `pane` in two, placing `new_pane` after it.
+    ///
+    /// Returns false if `pane` is not part of the layout.
+    pub fn split(&mut self, pane: uint, new_pane: uint, orientation: Orientation) -> bool {
+        match *self {
+            Layout::Pane(index) if index == pane => {}
+            Layout::Pane(_) => return false,
+            Layout::Split(_, ref mut first, ref mut second) => {
+                return first.split(pane, new_pane, orientation.clone())
+                    || second.split(pane, new_pane, orientation)
+            }
+        }
+
+        *self = Layout::Split(orientation, box Layout::Pane(pane), box Layout::Pane(new_pane));
+        true
+    }
+
+    /// Remove `pane` from the layout, giving its area to its sibling.
+    ///
+    /// Panes with a higher index are renumbered to fill the gap, in the same
+    /// way as removing an element from a Vec.
+    pub fn remove(&mut self, pane: uint) {
+        self.unlink(pane);
+        self.renumber(pane);
+    }
+
+    fn unlink(&mut self, pane: uint) {
+        let sibling = match *self {
+            Layout::Pane(_) => return,
+            Layout::Split(_, ref mut first, ref mut second) => {
+                if **first == Layout::Pane(pane) {
+                    Some((**second).clone())
+                } else if **second == Layout::Pane(pane) {
+                    Some((**first).clone())
+                } else {
+                    first.unlink(pane);
+                    second.unlink(pane);
+                    None
+                }
+            }
+        };
+
+        if let Some(layout) = sibling {
+            *self = layout;
+        }
+    }
+
+    fn renumber(&mut self, removed: uint) {
+        match *self {
+            Layout::Pane(ref mut index) => {
+                if *index > removed {
+                    *index -= 1;
+                }
+            }
+            Layout::Split(_, ref mut first, ref mut second) => {
+                first.renumber(removed);
+                second.renumber(removed);
+            }
+        }
+    }
+
+    /// Get the index of every pane in the layout, from top-left to bottom-right
+    pub fn panes(&self) -> Vec<uint> {
+        match *self {
+            Layout::Pane(index) => vec![index],
+            Layout::Split(_, ref first, ref second) => {
+                let mut panes = first.panes();
+                panes.push_all(second.panes().as_slice());
+                panes
+            }
+        }
+    }
+
+    /// Divide `area` between the panes in the layout.
+    ///
+    /// The area given to each pane is added to `panes`. Panes placed side by
+    /// side are divided by a one column separator, which is added to
+    /// `separators`.
+    pub fn arrange(&self, area: Rect, panes: &mut Vec<(uint, Rect)>, separators: &mut Vec<Rect>) {
+        match *self {
+            Layout::Pane(index) => panes.push((index, area)),
+            Layout::Split(ref orientation, ref first, ref second) => {
+                match *orientation {
+                    Orientation::Horizontal => {
+                        let height = area.height / 2;
+                        let first_area = Rect::new(area.x, area.y, area.width, height);
+                        let second_area = Rect::new(area.x, area.y + height,
+                                                    area.width, area.height - height);
+
+                        first.arrange(first_area, panes, separators);
+                        second.arrange(second_area, panes, separators);
+                    }
+                    Orientation::Vertical => {
+                        // the terminal may have shrunk since the split was
+                        // made, leaving no room for the separator
+                        let width = area.width.saturating_sub(1) / 2;
+                        let first_area = Rect::new(area.x, area.y, width, area.height);
+                        let second_area = Rect::new(area.x + width + 1, area.y,
+                                                    area.width.saturating_sub(width + 1), area.height);
+
+                        if area.width > 0 {
+                            separators.push(Rect::new(area.x + width, area.y, 1, area.height));
+                        }
+                        first.arrange(first_area, panes, separators);
+                        second.arrange(second_area, panes, separators);
+                    }
+                }
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use layout::{Layout, Orientation, Rect};
+
+    fn arrange(layout: &Layout, area: Rect) -> (Vec<(uint, Rect)>, Vec<Rect>) {
+        let mut panes = Vec::new();
+        let mut separators = Vec::new();
+        layout.arrange(area, &mut panes, &mut separators);
+        (panes, separators)
+    }
+
+    #[test]
+    fn single_pane_fills_the_area() {
+        let layout = Layout::Pane(0);
+        let (panes, separators) = arrange(&layout, Rect::new(0, 0, 80, 24));
+
+        assert_eq!(panes, vec![(0, Rect::new(0, 0, 80, 24))]);
+        assert!(separators.is_empty());
+    }
+
+    #[test]
+    fn rect_contains_the_points_inside_its_edges() {
+        let rect = Rect::new(41, 0, 40, 24);
+
+        assert!(rect.contains(41, 0));
+        assert!(rect.contains(80, 23));
+        assert!(!rect.contains(40, 10));
+        assert!(!rect.contains(81, 10));
+        assert!(!rect.contains(50, 24));
+    }
+
+    #[test]
+    fn horizontal_split_stacks_panes() {
+        let mut layout = Layout::Pane(0);
+        assert!(layout.split(0, 1, Orientation::Horizontal));
+        let (panes, _) = arrange(&layout, Rect::new(0, 0, 80, 25));
+
+        assert_eq!(panes, vec![(0, Rect::new(0, 0, 80, 12)), (1, Rect::new(0, 12, 80, 13))]);
+    }
+
+    #[test]
+    fn vertical_split_places_panes_side_by_side() {
+        let mut layout = Layout::Pane(0);
+        assert!(layout.split(0, 1, Orientation::Vertical));
+        let (panes, separators) = arrange(&layout, Rect::new(0, 0, 81, 24));
+
+        assert_eq!(panes, vec![(0, Rect::new(0, 0, 40, 24)), (1, Rect::new(41, 0, 40, 24))]);
+        assert_eq!(separators, vec![Rect::new(40, 0, 1, 24)]);
+    }
+
+    #[test]
+    fn splits_are_arranged_in_an_area_too_small_for_them() {
+        let mut layout = Layout::Pane(0);
+        assert!(layout.split(0, 1, Orientation::Vertical));
+        let (panes, separators) = arrange(&layout, Rect::new(0, 0, 0, 1));
+
+        assert_eq!(panes, vec![(0, Rect::new(0, 0, 0, 1)), (1, Rect::new(1, 0, 0, 1))]);
+        assert!(separators.is_empty());
+    }
+
+    #[test]
+    fn rects_too_small_for_two_panes_cannot_be_split() {
+        assert!(Rect::new(0, 0, 3, 4).can_split(&Orientation::Horizontal));
+        assert!(!Rect::new(0, 0, 3, 3).can_split(&Orientation::Horizontal));
+        assert!(Rect::new(0, 0, 3, 3).can_split(&Orientation::Vertical));
+        assert!(!Rect::new(0, 0, 2, 3).can_split(&Orientation::Vertical));
+    }
+
+    #[test]
+    fn splitting_a_missing_pane_does_nothing() {
+        let mut layout = Layout::Pane(0);
+
+        assert!(!layout.split(3, 1, Orientation::Vertical));
+        assert_eq!(layout, Layout::Pane(0));
+    }
+
+    #[test]
+    fn removing_a_pane_gives_its_area_to_its_sibling() {
+        let mut layout = Layout::Pane(0);
+        layout.split(0, 1, Orientation::Vertical);
+        layout.split(1, 2, Orientation::Horizontal);
+        layout.remove(1);
+
+        assert_eq!(layout, Layout::Split(Orientation::Vertical,
+                                         box Layout::Pane(0), box Layout::Pane(1)));
+    }
+
+    #[test]
+    fn panes_are_listed_in_screen_order() {
+        let mut layout = Layout::Pane(0);
+        layout.split(0, 1, Orientation::Vertical);
+        layout.split(0, 2, Orientation::Horizontal);
+
+        assert_eq!(layout.panes(), vec![0, 2, 1]);
+    }
+}
@@ -1,12 +1,31 @@
+extern crate regex;
+extern crate serialize;
+
+pub use clipboard::SystemClipboard;
 pub use editor::Editor;
 pub use input::Input;
+pub use options::Options;
+pub use search::CaseMatching;
+pub use view::LineNumbers;
 
 mod input;
 mod utils;
 mod buffer;
+mod clipboard;
+mod command;
 mod editor;
 mod cursor;
 mod keyboard;
+mod keymap;
+mod layout;
+mod modes;
+mod options;
+mod prompt;
+mod replace;
+mod rope;
+mod search;
+mod syntax;
+mod terminal;
 mod view;
 mod uibuf;
 
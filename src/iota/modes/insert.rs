@@ -0,0 +1,25 @@
+use command::Command;
+use cursor::Direction;
+use keyboard;
+use keyboard::Key;
+use modes::Mode;
+
+/// The default key bindings for insert mode.
+///
+/// Printable keys which aren't bound insert their char into the buffer.
+pub fn bindings() -> Vec<(Vec<Key>, Command)> {
+    vec![
+        (vec![keyboard::UP],              Command::MoveCursor(Direction::Up)),
+        (vec![keyboard::DOWN],            Command::MoveCursor(Direction::Down)),
+        (vec![keyboard::LEFT],            Command::MoveCursor(Direction::Left)),
+        (vec![keyboard::RIGHT],           Command::MoveCursor(Direction::Right)),
+        (vec![keyboard::ENTER],           Command::InsertLine),
+
+        // Tab inserts spaces up to the next tab stop, rather than a \t
+        (vec![keyboard::TAB],             Command::InsertTab),
+
+        (vec![keyboard::BACKSPACE],       Command::DeleteChar(Direction::Left)),
+        (vec![keyboard::DELETE],          Command::DeleteChar(Direction::Right)),
+        (vec![keyboard::ESC],             Command::SwitchMode(Mode::Normal)),
+    ]
+}
@@ -0,0 +1,124 @@
+//! Editing modes.
+//!
+//! Each mode has its own set of default key bindings, which the Keymap starts
+//! out with. Keys which aren't bound in the active mode fall back to the
+//! global bindings shared by every mode.
+
+use command::Command;
+use cursor::{Direction, Motion};
+use keyboard;
+use keyboard::Key;
+use layout::Orientation;
+use search::SearchDirection;
+
+mod insert;
+mod normal;
+mod visual;
+
+
+#[deriving(Clone, PartialEq, Eq, Hash, Show)]
+pub enum Mode {
+    /// Keys move the cursor and run commands, as in vi
+    Normal,
+    /// Keys insert text into the buffer
+    Insert,
+    /// Keys extend a selection from where the mode was started
+    Visual,
+}
+
+impl Mode {
+    /// Get the mode with the given name, as used in the keymap file
+    pub fn from_name(name: &str) -> Option<Mode> {
+        match name {
+            "normal" => Some(Mode::Normal),
+            "insert" => Some(Mode::Insert),
+            "visual" => Some(Mode::Visual),
+            _        => None,
+        }
+    }
+
+    /// Get the name to show in the status bar
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Mode::Normal => "NORMAL",
+            Mode::Insert => "INSERT",
+            Mode::Visual => "VISUAL",
+        }
+    }
+
+    /// Get the default key bindings for this mode
+    pub fn bindings(&self) -> Vec<(Vec<Key>, Command)> {
+        match *self {
+            Mode::Normal => normal::bindings(),
+            Mode::Insert => insert::bindings(),
+            Mode::Visual => visual::bindings(),
+        }
+    }
+
+    /// Get the Command to run for a key which isn't bound to anything.
+    ///
+    /// Printable keys insert their char in insert mode, and do nothing in
+    /// any other mode.
+    pub fn unbound_key(&self, key: &Key) -> Option<Command> {
+        match *self {
+            Mode::Insert => key.get_printable_char().map(|ch| Command::InsertChar(ch)),
+            _            => None,
+        }
+    }
+}
+
+
+/// Get the default key bindings shared by every mode, including the Emacs
+/// style chords starting with C-x
+pub fn global_bindings() -> Vec<(Vec<Key>, Command)> {
+    vec![
+        (vec![keyboard::CTRL_S],          Command::Save),
+        (vec![keyboard::CTRL_Q],          Command::Quit),
+        (vec![keyboard::CTRL_R],          Command::Resize),
+        (vec![keyboard::CTRL_Z],          Command::Undo),
+        (vec![keyboard::CTRL_Y],          Command::Redo),
+
+        (vec![keyboard::CTRL_O],          Command::OpenBuffer),
+        (vec![keyboard::CTRL_N],          Command::NextBuffer),
+        (vec![keyboard::CTRL_P],          Command::PrevBuffer),
+        (vec![keyboard::CTRL_W],          Command::CloseBuffer),
+        (vec![keyboard::CTRL_B],          Command::ListBuffers),
+
+        (vec![keyboard::CTRL_F],          Command::Search(SearchDirection::Forward)),
+        (vec![keyboard::CTRL_SPACE],      Command::SetMark),
+        (vec![keyboard::CTRL_K],          Command::Cut),
+        (vec![keyboard::CTRL_C],          Command::Copy),
+        (vec![keyboard::CTRL_V],          Command::Paste),
+
+        (vec![keyboard::HOME],            Command::Move(Motion::LineStart)),
+        (vec![keyboard::END],             Command::Move(Motion::LineEnd)),
+        (vec![keyboard::PGUP],            Command::ScrollPage(Direction::Up)),
+        (vec![keyboard::PGDN],            Command::ScrollPage(Direction::Down)),
+        (vec![keyboard::CTRL_L],          Command::Recenter),
+
+        (vec![keyboard::F5],              Command::SplitPane(Orientation::Horizontal)),
+        (vec![keyboard::F6],              Command::SplitPane(Orientation::Vertical)),
+        (vec![keyboard::F7],              Command::FocusNextPane),
+        (vec![keyboard::F8],              Command::ClosePane),
+        (vec![keyboard::F9],              Command::ToggleWrap),
+        (vec![keyboard::F10],             Command::CycleLineEnding),
+        (vec![keyboard::F11],             Command::CycleLineNumbers),
+
+        (vec![keyboard::CTRL_X, keyboard::CTRL_S],      Command::Save),
+        (vec![keyboard::CTRL_X, keyboard::CTRL_C],      Command::Quit),
+        (vec![keyboard::CTRL_X, keyboard::CTRL_F],      Command::OpenFile),
+        (vec![keyboard::CTRL_X, Key::from_char('b')],   Command::ListBuffers),
+        (vec![keyboard::CTRL_X, Key::from_char('k')],   Command::CloseBuffer),
+        (vec![keyboard::CTRL_X, Key::from_char('2')],   Command::SplitPane(Orientation::Horizontal)),
+        (vec![keyboard::CTRL_X, Key::from_char('3')],   Command::SplitPane(Orientation::Vertical)),
+        (vec![keyboard::CTRL_X, Key::from_char('o')],   Command::FocusNextPane),
+        (vec![keyboard::CTRL_X, Key::from_char('0')],   Command::ClosePane),
+        (vec![keyboard::CTRL_X, Key::from_char('%')],   Command::Replace),
+        (vec![keyboard::CTRL_X, Key::from_char('#')],   Command::ReplaceLines),
+        (vec![keyboard::CTRL_X, Key::from_char('y')],   Command::PastePrevious),
+        (vec![keyboard::CTRL_X, Key::from_char('g')],   Command::GoToLine),
+        (vec![keyboard::CTRL_X, keyboard::UP],          Command::ScrollLine(Direction::Up)),
+        (vec![keyboard::CTRL_X, keyboard::DOWN],        Command::ScrollLine(Direction::Down)),
+        (vec![keyboard::CTRL_X, Key::from_char('l')],   Command::ScrollLines),
+    ]
+}
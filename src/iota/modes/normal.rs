@@ -0,0 +1,57 @@
+use command::Command;
+use cursor::{Direction, Motion};
+use keyboard;
+use keyboard::Key;
+use modes::Mode;
+use search::SearchDirection;
+
+/// The default key bindings for normal mode, which follow vi.
+///
+/// Printable keys which aren't bound do nothing.
+pub fn bindings() -> Vec<(Vec<Key>, Command)> {
+    vec![
+        (vec![Key::from_char('h')],       Command::MoveCursor(Direction::Left)),
+        (vec![Key::from_char('j')],       Command::MoveCursor(Direction::Down)),
+        (vec![Key::from_char('k')],       Command::MoveCursor(Direction::Up)),
+        (vec![Key::from_char('l')],       Command::MoveCursor(Direction::Right)),
+        (vec![Key::from_char('w')],       Command::Move(Motion::NextWord)),
+        (vec![Key::from_char('b')],       Command::Move(Motion::PrevWord)),
+        (vec![Key::from_char('e')],       Command::Move(Motion::WordEnd)),
+        (vec![Key::from_char('0')],       Command::Move(Motion::LineStart)),
+        (vec![Key::from_char('^')],       Command::Move(Motion::FirstNonBlank)),
+        (vec![Key::from_char('$')],       Command::Move(Motion::LineEnd)),
+        (vec![Key::from_char('}')],       Command::Move(Motion::NextParagraph)),
+        (vec![Key::from_char('{')],       Command::Move(Motion::PrevParagraph)),
+        (vec![Key::from_char('G')],       Command::Move(Motion::BufferEnd)),
+        (vec![Key::from_char('g'), Key::from_char('g')], Command::Move(Motion::BufferStart)),
+        (vec![Key::from_char('z'), Key::from_char('z')], Command::Recenter),
+
+        (vec![Key::from_char('i')],       Command::SwitchMode(Mode::Insert)),
+        (vec![Key::from_char('a')],       Command::Append),
+        (vec![Key::from_char('o')],       Command::OpenLineBelow),
+        (vec![Key::from_char('v')],       Command::SwitchMode(Mode::Visual)),
+
+        (vec![Key::from_char('x')],       Command::DeleteChar(Direction::Right)),
+        (vec![Key::from_char('X')],       Command::DeleteChar(Direction::Left)),
+        (vec![Key::from_char('u')],       Command::Undo),
+        (vec![Key::from_char('p')],       Command::Paste),
+
+        (vec![Key::from_char('/')],       Command::Search(SearchDirection::Forward)),
+        (vec![Key::from_char('?')],       Command::Search(SearchDirection::Backward)),
+        (vec![Key::from_char('n')],       Command::SearchNext),
+        (vec![Key::from_char('N')],       Command::SearchPrev),
+        (vec![Key::from_char('d'), Key::from_char('d')], Command::DeleteLine),
+        (vec![Key::from_char('Z'), Key::from_char('Q')], Command::ForceQuit),
+        (vec![Key::from_char('y'), Key::from_char('y')], Command::Copy),
+        (vec![Key::from_char('>'), Key::from_char('>')], Command::Indent),
+        (vec![Key::from_char('<'), Key::from_char('<')], Command::Dedent),
+
+        (vec![keyboard::UP],              Command::MoveCursor(Direction::Up)),
+        (vec![keyboard::DOWN],            Command::MoveCursor(Direction::Down)),
+        (vec![keyboard::LEFT],            Command::MoveCursor(Direction::Left)),
+        (vec![keyboard::RIGHT],           Command::MoveCursor(Direction::Right)),
+        (vec![keyboard::ENTER],           Command::MoveCursor(Direction::Down)),
+        (vec![keyboard::BACKSPACE],       Command::MoveCursor(Direction::Left)),
+        (vec![keyboard::DELETE],          Command::DeleteChar(Direction::Right)),
+    ]
+}
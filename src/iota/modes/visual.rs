@@ -0,0 +1,43 @@
+use command::Command;
+use cursor::{Direction, Motion};
+use keyboard;
+use keyboard::Key;
+use modes::Mode;
+
+/// The default key bindings for visual mode.
+///
+/// Moving the cursor extends the selection, which runs from where visual
+/// mode was started to the cursor.
+pub fn bindings() -> Vec<(Vec<Key>, Command)> {
+    vec![
+        (vec![Key::from_char('h')],       Command::MoveCursor(Direction::Left)),
+        (vec![Key::from_char('j')],       Command::MoveCursor(Direction::Down)),
+        (vec![Key::from_char('k')],       Command::MoveCursor(Direction::Up)),
+        (vec![Key::from_char('l')],       Command::MoveCursor(Direction::Right)),
+        (vec![Key::from_char('w')],       Command::Move(Motion::NextWord)),
+        (vec![Key::from_char('b')],       Command::Move(Motion::PrevWord)),
+        (vec![Key::from_char('e')],       Command::Move(Motion::WordEnd)),
+        (vec![Key::from_char('0')],       Command::Move(Motion::LineStart)),
+        (vec![Key::from_char('^')],       Command::Move(Motion::FirstNonBlank)),
+        (vec![Key::from_char('$')],       Command::Move(Motion::LineEnd)),
+        (vec![Key::from_char('}')],       Command::Move(Motion::NextParagraph)),
+        (vec![Key::from_char('{')],       Command::Move(Motion::PrevParagraph)),
+        (vec![Key::from_char('G')],       Command::Move(Motion::BufferEnd)),
+        (vec![Key::from_char('g'), Key::from_char('g')], Command::Move(Motion::BufferStart)),
+
+        (vec![Key::from_char('d')],       Command::DeleteSelection),
+        (vec![Key::from_char('x')],       Command::DeleteSelection),
+        (vec![Key::from_char('y')],       Command::Copy),
+        (vec![Key::from_char('>')],       Command::Indent),
+        (vec![Key::from_char('<')],       Command::Dedent),
+        (vec![Key::from_char('v')],       Command::SwitchMode(Mode::Normal)),
+
+        (vec![keyboard::UP],              Command::MoveCursor(Direction::Up)),
+        (vec![keyboard::DOWN],            Command::MoveCursor(Direction::Down)),
+        (vec![keyboard::LEFT],            Command::MoveCursor(Direction::Left)),
+        (vec![keyboard::RIGHT],           Command::MoveCursor(Direction::Right)),
+        (vec![keyboard::BACKSPACE],       Command::DeleteSelection),
+        (vec![keyboard::DELETE],          Command::DeleteSelection),
+        (vec![keyboard::ESC],             Command::SwitchMode(Mode::Normal)),
+    ]
+}
@@ -0,0 +1,46 @@
+use search::CaseMatching;
+use view::LineNumbers;
+
+/// Settings which control how a View displays and edits its Buffer.
+#[deriving(Clone)]
+pub struct Options {
+    /// Number of columns between tab stops
+    pub tab_width: uint,
+
+    /// Number of columns to keep between the cursor and the left or right
+    /// edge of a View when scrolling horizontally
+    pub horizontal_margin: uint,
+
+    /// Whether long lines are wrapped across several rows, rather than
+    /// scrolling the View horizontally
+    pub wrap: bool,
+
+    /// How the lines are numbered in the gutter
+    pub line_numbers: LineNumbers,
+
+    /// Chars besides letters and digits which word motions count as part of
+    /// a word
+    pub word_chars: String,
+
+    /// How letters in a search match letters of the other case
+    pub case_matching: CaseMatching,
+
+    /// Whether the mouse can be used to move the cursor, select text and
+    /// scroll
+    pub mouse: bool,
+}
+
+impl Options {
+    /// Create a new set of options with the default settings
+    pub fn new() -> Options {
+        Options {
+            tab_width: 4,
+            horizontal_margin: 5,
+            wrap: false,
+            line_numbers: LineNumbers::Off,
+            word_chars: String::from_str("_"),
+            case_matching: CaseMatching::Smart,
+            mouse: true,
+        }
+    }
+}
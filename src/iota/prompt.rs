@@ -0,0 +1,336 @@
+use keyboard;
+use keyboard::Key;
+
+// how many entries each History keeps
+const HISTORY_SIZE: uint = 100;
+
+
+/// What to do with the text entered into a Prompt, once it is confirmed
+#[deriving(Clone, PartialEq, Eq, Hash, Show)]
+pub enum PromptAction {
+    /// Save the active buffer to the path entered
+    SaveAs,
+    /// Open the file at the path entered in a new buffer
+    OpenFile,
+    /// Move to the text entered, searching as it is typed
+    Search,
+    /// Move the cursor to the line, or line and column, entered
+    GoToLine,
+    /// Scroll by the number of lines entered
+    ScrollLines,
+    /// Replace within the range of lines entered
+    ReplaceLines,
+    /// Replace the matches for the regex entered
+    ReplacePattern,
+    /// Replace the matches with the text entered
+    ReplaceWith,
+    /// Ask whether to replace each match, taking single key answers
+    ConfirmReplace,
+    /// Ask whether to quit when there are unsaved changes, taking a single
+    /// key answer
+    ConfirmQuit,
+    /// Ask whether to close a buffer with unsaved changes, taking a single
+    /// key answer
+    ConfirmClose,
+}
+
+
+/// What a key press did to a Prompt
+#[deriving(Clone, PartialEq, Show)]
+pub enum PromptStatus {
+    /// The text entered was changed
+    Edited,
+    /// The text entered is the same, though the cursor may have moved
+    Unchanged,
+    /// The text was confirmed with Enter
+    Confirmed,
+    /// The prompt was cancelled with Esc or C-g
+    Cancelled,
+}
+
+
+/// The text confirmed in earlier prompts, oldest first
+#[deriving(Clone)]
+pub struct History {
+    entries: Vec<String>,
+}
+
+impl History {
+    pub fn new() -> History {
+        History { entries: Vec::new() }
+    }
+
+    /// Add `text` as the newest entry, moving it to the end if it is
+    /// already there
+    pub fn add(&mut self, text: &str) {
+        if text.is_empty() { return }
+
+        if let Some(index) = self.entries.iter().position(|entry| entry.as_slice() == text) {
+            self.entries.remove(index);
+        }
+        self.entries.push(String::from_str(text));
+        if self.entries.len() > HISTORY_SIZE {
+            self.entries.remove(0);
+        }
+    }
+
+    pub fn len(&self) -> uint {
+        self.entries.len()
+    }
+
+    pub fn get(&self, index: uint) -> &str {
+        self.entries[index].as_slice()
+    }
+}
+
+
+/// A single line of text input.
+///
+/// While a Prompt is open it takes over the status line of the active pane,
+/// and every key press goes to it until the text is confirmed with Enter or
+/// the prompt is cancelled. The text can be edited anywhere along the line,
+/// and Up and Down bring back text confirmed in earlier prompts.
+pub struct Prompt {
+    pub action: PromptAction,
+    label: String,
+    text: String,
+    // byte offset of the cursor in the text
+    cursor: uint,
+
+    history: History,
+    // the history entry being shown, or None for the text being typed
+    history_index: Option<uint>,
+    // the text being typed, kept while looking through the history
+    draft: String,
+}
+
+impl Prompt {
+    pub fn new(label: &str, action: PromptAction, history: History) -> Prompt {
+        Prompt {
+            action: action,
+            label: String::from_str(label),
+            text: String::new(),
+            cursor: 0,
+            history: history,
+            history_index: None,
+            draft: String::new(),
+        }
+    }
+
+    /// Get the text entered so far
+    pub fn get_text(&self) -> &str {
+        self.text.as_slice()
+    }
+
+    pub fn set_label(&mut self, label: &str) {
+        self.label = String::from_str(label);
+    }
+
+    /// Edit the text, move the cursor or close the prompt in response to a
+    /// key press
+    pub fn handle_key(&mut self, key: Key) -> PromptStatus {
+        let before = self.text.clone();
+
+        match key {
+            keyboard::ENTER                   => return PromptStatus::Confirmed,
+            keyboard::ESC | keyboard::CTRL_G  => return PromptStatus::Cancelled,
+
+            keyboard::BACKSPACE               => self.delete_char(),
+            keyboard::DELETE                  => self.delete_forward_char(),
+            keyboard::LEFT                    => self.move_left(),
+            keyboard::RIGHT                   => self.move_right(),
+            keyboard::HOME | keyboard::CTRL_A => self.cursor = 0,
+            keyboard::END | keyboard::CTRL_E  => self.cursor = self.text.len(),
+            keyboard::UP                      => self.history_prev(),
+            keyboard::DOWN                    => self.history_next(),
+
+            _ => match key.get_printable_char() {
+                Some(ch) => self.insert_char(ch),
+                None     => {}
+            },
+        }
+
+        if self.text == before { PromptStatus::Unchanged } else { PromptStatus::Edited }
+    }
+
+    /// Insert `ch` at the cursor
+    pub fn insert_char(&mut self, ch: char) {
+        let mut text = String::from_str(self.text.as_slice().slice_to(self.cursor));
+        text.push(ch);
+        let cursor = text.len();
+        text.push_str(self.text.as_slice().slice_from(self.cursor));
+
+        self.cursor = cursor;
+        self.text = text;
+    }
+
+    /// Delete the grapheme before the cursor
+    pub fn delete_char(&mut self) {
+        let (start, end) = (self.prev_boundary(), self.cursor);
+        self.remove(start, end);
+        self.cursor = start;
+    }
+
+    /// Delete the grapheme under the cursor
+    pub fn delete_forward_char(&mut self) {
+        let (start, end) = (self.cursor, self.next_boundary());
+        self.remove(start, end);
+    }
+
+    pub fn move_left(&mut self) {
+        self.cursor = self.prev_boundary();
+    }
+
+    pub fn move_right(&mut self) {
+        self.cursor = self.next_boundary();
+    }
+
+    /// Replace the text with the previous history entry
+    pub fn history_prev(&mut self) {
+        let index = match self.history_index {
+            Some(0) => return,
+            Some(index) => index - 1,
+            None if self.history.len() == 0 => return,
+            None => {
+                self.draft = self.text.clone();
+                self.history.len() - 1
+            }
+        };
+        self.show_history(Some(index));
+    }
+
+    /// Replace the text with the next history entry, or with the text that
+    /// was being typed after the newest one
+    pub fn history_next(&mut self) {
+        let index = match self.history_index {
+            None => return,
+            Some(index) if index + 1 < self.history.len() => Some(index + 1),
+            Some(_) => None,
+        };
+        self.show_history(index);
+    }
+
+    /// Get the label followed by the text entered so far
+    pub fn get_display_text(&self) -> String {
+        format!("{}{}", self.label, self.text)
+    }
+
+    /// Get the screen column of the cursor
+    pub fn get_cursor_column(&self) -> uint {
+        let before_cursor = self.text.as_slice().slice_to(self.cursor);
+        self.label.as_slice().width(false) + before_cursor.width(false)
+    }
+
+    fn show_history(&mut self, index: Option<uint>) {
+        self.text = match index {
+            Some(index) => String::from_str(self.history.get(index)),
+            None        => self.draft.clone(),
+        };
+        self.history_index = index;
+        self.cursor = self.text.len();
+    }
+
+    fn remove(&mut self, start: uint, end: uint) {
+        let mut text = String::from_str(self.text.as_slice().slice_to(start));
+        text.push_str(self.text.as_slice().slice_from(end));
+        self.text = text;
+    }
+
+    fn prev_boundary(&self) -> uint {
+        match self.text.as_slice().slice_to(self.cursor).graphemes(true).last() {
+            Some(grapheme) => self.cursor - grapheme.len(),
+            None           => self.cursor,
+        }
+    }
+
+    fn next_boundary(&self) -> uint {
+        match self.text.as_slice().slice_from(self.cursor).graphemes(true).next() {
+            Some(grapheme) => self.cursor + grapheme.len(),
+            None           => self.cursor,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use keyboard;
+    use prompt::{History, Prompt, PromptAction, PromptStatus};
+
+    fn setup_prompt() -> Prompt {
+        let mut prompt = Prompt::new("Save as: ", PromptAction::SaveAs, History::new());
+        for ch in "ab漢".chars() {
+            prompt.insert_char(ch);
+        }
+        prompt
+    }
+
+    #[test]
+    fn typed_chars_follow_the_label() {
+        let prompt = setup_prompt();
+
+        assert_eq!(prompt.get_text(), "ab漢");
+        assert_eq!(prompt.get_display_text(), String::from_str("Save as: ab漢"));
+        assert_eq!(prompt.get_cursor_column(), 13);
+    }
+
+    #[test]
+    fn delete_char_removes_the_last_grapheme() {
+        let mut prompt = setup_prompt();
+        prompt.delete_char();
+        assert_eq!(prompt.get_text(), "ab");
+
+        prompt.delete_char();
+        prompt.delete_char();
+        prompt.delete_char();
+        assert_eq!(prompt.get_text(), "");
+    }
+
+    #[test]
+    fn text_can_be_edited_at_the_cursor() {
+        let mut prompt = setup_prompt();
+        assert_eq!(prompt.handle_key(keyboard::LEFT), PromptStatus::Unchanged);
+        assert_eq!(prompt.get_cursor_column(), 11);
+
+        prompt.insert_char('c');
+        assert_eq!(prompt.get_text(), "abc漢");
+
+        prompt.handle_key(keyboard::HOME);
+        assert_eq!(prompt.handle_key(keyboard::DELETE), PromptStatus::Edited);
+        assert_eq!(prompt.get_text(), "bc漢");
+        assert_eq!(prompt.handle_key(keyboard::BACKSPACE), PromptStatus::Unchanged);
+
+        prompt.handle_key(keyboard::CTRL_E);
+        prompt.handle_key(keyboard::BACKSPACE);
+        assert_eq!(prompt.get_text(), "bc");
+        assert_eq!(prompt.handle_key(keyboard::ENTER), PromptStatus::Confirmed);
+        assert_eq!(prompt.handle_key(keyboard::CTRL_G), PromptStatus::Cancelled);
+    }
+
+    #[test]
+    fn up_and_down_go_through_the_history() {
+        let mut history = History::new();
+        history.add("one");
+        history.add("two");
+        history.add("one");
+        history.add("");
+        assert_eq!(history.len(), 2);
+
+        let mut prompt = Prompt::new("Open: ", PromptAction::OpenFile, history);
+        prompt.insert_char('x');
+
+        prompt.handle_key(keyboard::UP);
+        assert_eq!(prompt.get_text(), "one");
+        prompt.handle_key(keyboard::UP);
+        assert_eq!(prompt.get_text(), "two");
+        prompt.handle_key(keyboard::UP);
+        assert_eq!(prompt.get_text(), "two");
+
+        prompt.handle_key(keyboard::DOWN);
+        assert_eq!(prompt.get_text(), "one");
+        prompt.handle_key(keyboard::DOWN);
+        assert_eq!(prompt.get_text(), "x");
+        assert_eq!(prompt.get_cursor_column(), 7);
+    }
+}
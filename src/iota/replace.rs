@@ -0,0 +1,331 @@
+//! Replacing text in a Buffer which matches a regular expression.
+
+use std::cmp;
+
+use regex::{Captures, Regex};
+
+use buffer::{Buffer, Change, Operation};
+use view::Region;
+
+/// A match for the regex of a Replace, along with the text to put in its place
+pub struct Match {
+    pub region: Region,
+    pub replacement: String,
+}
+
+
+/// Replaces the matches for a regular expression within a region of a
+/// Buffer, one at a time so that each one can be confirmed.
+///
+/// Matches don't span lines. In the replacement, `$1` or `${name}` stands for
+/// the text captured by a group of the regex, and `$$` for a single `$`.
+pub struct Replace {
+    regex: Regex,
+    // the regex after any one char, only matching at the start of the text
+    anchored: Regex,
+    replacement: String,
+    // where to look for the next match from
+    next: (uint, uint),
+    // the end of the region, which moves as replacements on its last line
+    // change the length of the line
+    end: (uint, uint),
+    // the end of the last match, where an empty match isn't allowed
+    last_end: Option<(uint, uint)>,
+
+    /// The number of matches replaced so far
+    pub count: uint,
+}
+
+impl Replace {
+    pub fn new(regex: Regex, replacement: &str, region: Region) -> Replace {
+        let (start, end) = region;
+        // wrapping a regex which compiled can't make it invalid
+        let anchored = format!(r"\A(?s:.)(?:{})", regex.as_str());
+        Replace {
+            anchored: Regex::new(anchored.as_slice()).unwrap(),
+            regex: regex,
+            replacement: String::from_str(replacement),
+            next: start,
+            end: end,
+            last_end: None,
+            count: 0,
+        }
+    }
+
+    /// Find the next match in the region, without changing anything
+    pub fn find_next(&self, buffer: &Buffer) -> Option<Match> {
+        let (mut from_offset, first_line) = self.next;
+        let (end_offset, end_line) = self.end;
+        let last_line = cmp::min(end_line + 1, buffer.line_count());
+
+        for line_num in range(first_line, last_line) {
+            let data = buffer.get_line(line_num).data.as_slice();
+            let limit = if line_num == end_line {
+                cmp::min(end_offset, data.len())
+            } else {
+                data.len()
+            };
+
+            if !self.regex.is_match(data) {
+                from_offset = 0;
+                continue
+            }
+
+            // try each position in turn, as searching from the start of the
+            // line would skip matches overlapping one before `from_offset`
+            let mut offset = from_offset;
+            while offset <= limit {
+                if let Some((end, captures)) = self.match_at(data, offset) {
+                    let empty_again = offset == end && self.last_end == Some((offset, line_num));
+                    if end <= limit && !empty_again {
+                        return Some(Match {
+                            region: ((offset, line_num), (end, line_num)),
+                            replacement: captures.expand(self.replacement.as_slice()),
+                        })
+                    }
+                }
+                if offset == data.len() { break }
+                offset = data.char_range_at(offset).next;
+            }
+            from_offset = 0;
+        }
+        None
+    }
+
+    /// Match the regex starting at exactly `offset` in the text of a line,
+    /// returning where the match ends. The text before `offset` is still seen,
+    /// so that anchors and word boundaries work as they do for the whole line.
+    fn match_at<'t>(&self, data: &'t str, offset: uint) -> Option<(uint, Captures<'t>)> {
+        let (base, captures) = if offset == 0 {
+            (0, self.regex.captures(data))
+        } else {
+            let base = data.char_range_at_reverse(offset).next;
+            (base, self.anchored.captures(data.slice_from(base)))
+        };
+        match captures {
+            Some(captures) => match captures.pos(0) {
+                Some((0, end)) => Some((base + end, captures)),
+                _              => None,
+            },
+            None => None,
+        }
+    }
+
+    /// Replace a match found by `find_next`, recording the change so that it
+    /// can be undone, and move past it.
+    ///
+    /// Returns the position just past the replacement.
+    pub fn replace(&mut self, buffer: &mut Buffer, found: &Match) -> (uint, uint) {
+        let (start, end) = found.region;
+        let (removed, text_end) = buffer.replace_text(start, end, found.replacement.as_slice());
+        buffer.record(Operation {
+            change: Change::Replace(removed, found.replacement.clone()),
+            position: start,
+            cursor_before: start,
+            cursor_after: text_end,
+        });
+
+        let (_, line_num) = start;
+        let (match_end, _) = end;
+        let (text_end_offset, _) = text_end;
+        let (end_offset, end_line) = self.end;
+        if line_num == end_line {
+            self.end = (end_offset + text_end_offset - match_end, end_line);
+        }
+
+        self.move_past(buffer, start == end, text_end);
+        self.count += 1;
+        text_end
+    }
+
+    /// Leave a match found by `find_next` as it is, and move past it
+    pub fn skip(&mut self, buffer: &Buffer, found: &Match) {
+        let (start, end) = found.region;
+        self.move_past(buffer, start == end, end);
+    }
+
+    /// Replace every match left in the region
+    pub fn replace_all(&mut self, buffer: &mut Buffer) {
+        loop {
+            let found = match self.find_next(buffer) {
+                Some(found) => found,
+                None        => break,
+            };
+            self.replace(buffer, &found);
+        }
+    }
+
+    /// Look for the next match from `end`. After an empty match the char
+    /// following it is stepped over too, so that the match isn't found again.
+    fn move_past(&mut self, buffer: &Buffer, empty: bool, end: (uint, uint)) {
+        let (offset, line_num) = end;
+        self.last_end = Some(end);
+        self.next = if !empty {
+            end
+        } else {
+            let data = buffer.get_line(line_num).data.as_slice();
+            if offset < data.len() {
+                (data.char_range_at(offset).next, line_num)
+            } else {
+                (0, line_num + 1)
+            }
+        };
+    }
+}
+
+
+/// Get the region covering the whole of `buffer`
+pub fn whole_buffer(buffer: &Buffer) -> Region {
+    let last_line = buffer.line_count() - 1;
+    ((0, 0), (buffer.get_line(last_line).len(), last_line))
+}
+
+/// Read a range of lines written as `first,last`, or a single line number,
+/// counting from 1. Returns the region from the start of the first line to
+/// the end of the last, or None if the lines aren't in the buffer.
+pub fn parse_line_range(text: &str, buffer: &Buffer) -> Option<Region> {
+    let numbers: Vec<Option<uint>> = text.split(',').map(|n| from_str(n.trim())).collect();
+    let (first, last) = match numbers.as_slice() {
+        [Some(line)]              => (line, line),
+        [Some(first), Some(last)] => (first, last),
+        _                         => return None,
+    };
+
+    if first == 0 || first > last || last > buffer.line_count() {
+        return None
+    }
+    Some(((0, first - 1), (buffer.get_line(last - 1).len(), last - 1)))
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use regex::Regex;
+
+    use buffer::{Buffer, Line};
+    use replace::{Replace, parse_line_range, whole_buffer};
+    use rope::Rope;
+    use utils::data_from_str;
+
+    fn setup_buffer() -> Buffer {
+        let mut buffer = Buffer::new();
+        buffer.lines = Rope::from_vec(vec!(
+            Line::new(data_from_str("one fish")),
+            Line::new(data_from_str("two fish")),
+            Line::new(data_from_str("red fish, blue fish")),
+        ));
+        buffer
+    }
+
+    fn lines(buffer: &Buffer) -> Vec<String> {
+        buffer.lines.iter().map(|line| line.data.clone()).collect()
+    }
+
+    #[test]
+    fn replace_all_expands_captures() {
+        let mut buffer = setup_buffer();
+        let region = whole_buffer(&buffer);
+        let mut replace = Replace::new(Regex::new(r"(\w+) fish").unwrap(), "$1 cat", region);
+        replace.replace_all(&mut buffer);
+
+        assert_eq!(replace.count, 4);
+        assert_eq!(lines(&buffer), vec![
+            data_from_str("one cat"),
+            data_from_str("two cat"),
+            data_from_str("red cat, blue cat"),
+        ]);
+
+        buffer.undo();
+        assert_eq!(buffer.get_line(2).data, data_from_str("red cat, blue fish"));
+    }
+
+    #[test]
+    fn matches_can_be_skipped() {
+        let mut buffer = setup_buffer();
+        let region = whole_buffer(&buffer);
+        let mut replace = Replace::new(Regex::new("fish").unwrap(), "dog", region);
+
+        let found = replace.find_next(&buffer).unwrap();
+        assert_eq!(found.region, ((4, 0), (8, 0)));
+        replace.skip(&buffer, &found);
+
+        let found = replace.find_next(&buffer).unwrap();
+        assert_eq!(found.region, ((4, 1), (8, 1)));
+        assert_eq!(replace.replace(&mut buffer, &found), (7, 1));
+
+        let found = replace.find_next(&buffer).unwrap();
+        assert_eq!(found.region, ((4, 2), (8, 2)));
+        assert_eq!(replace.count, 1);
+        assert_eq!(buffer.get_line(1).data, data_from_str("two dog"));
+    }
+
+    #[test]
+    fn only_the_region_is_changed() {
+        let mut buffer = setup_buffer();
+        let region = ((0, 1), (13, 2));
+        let mut replace = Replace::new(Regex::new("fish").unwrap(), "fishes", region);
+        replace.replace_all(&mut buffer);
+
+        assert_eq!(replace.count, 2);
+        assert_eq!(lines(&buffer), vec![
+            data_from_str("one fish"),
+            data_from_str("two fishes"),
+            data_from_str("red fishes, blue fish"),
+        ]);
+    }
+
+    #[test]
+    fn empty_matches_are_replaced_once() {
+        let mut buffer = setup_buffer();
+        let region = ((0, 0), (3, 0));
+        let mut replace = Replace::new(Regex::new("x*").unwrap(), "-", region);
+        replace.replace_all(&mut buffer);
+        assert_eq!(buffer.get_line(0).data, data_from_str("-o-n-e- fish"));
+
+        let region = whole_buffer(&buffer);
+        let mut replace = Replace::new(Regex::new("^").unwrap(), "# ", region);
+        replace.replace_all(&mut buffer);
+        assert_eq!(replace.count, 3);
+        assert_eq!(buffer.get_line(1).data, data_from_str("# two fish"));
+    }
+
+    #[test]
+    fn matches_overlapping_an_earlier_one_are_found() {
+        let mut buffer = Buffer::new();
+        buffer.lines = Rope::from_vec(vec!(Line::new(data_from_str("aa"))));
+        let region = whole_buffer(&buffer);
+        let mut replace = Replace::new(Regex::new("xa|a").unwrap(), "x", region);
+        replace.replace_all(&mut buffer);
+
+        assert_eq!(replace.count, 2);
+        assert_eq!(buffer.get_line(0).data, data_from_str("xx"));
+    }
+
+    #[test]
+    fn anchors_dont_match_at_the_end_of_the_region() {
+        let mut buffer = setup_buffer();
+        let region = ((0, 0), (6, 0));
+        let mut replace = Replace::new(Regex::new(r"fi\b|fi$").unwrap(), "--", region);
+        assert!(replace.find_next(&buffer).is_none());
+        replace.replace_all(&mut buffer);
+        assert_eq!(replace.count, 0);
+
+        let region = ((0, 0), (8, 0));
+        let mut replace = Replace::new(Regex::new(r"fish$").unwrap(), "cat", region);
+        replace.replace_all(&mut buffer);
+        assert_eq!(buffer.get_line(0).data, data_from_str("one cat"));
+    }
+
+    #[test]
+    fn line_ranges_count_from_one() {
+        let buffer = setup_buffer();
+
+        assert_eq!(parse_line_range("2", &buffer), Some(((0, 1), (8, 1))));
+        assert_eq!(parse_line_range(" 1, 3 ", &buffer), Some(((0, 0), (19, 2))));
+        assert_eq!(parse_line_range("0", &buffer), None);
+        assert_eq!(parse_line_range("3,2", &buffer), None);
+        assert_eq!(parse_line_range("2,4", &buffer), None);
+        assert_eq!(parse_line_range("two", &buffer), None);
+    }
+}
@@ -0,0 +1,413 @@
+use std::cmp;
+
+/// A sequence of values held in a balanced binary tree.
+///
+/// Each node knows how many values are in its subtree, so a value can be
+/// looked up, inserted or removed by its index in O(log n) time. Buffer uses
+/// this to hold its lines, so that large files can be edited without
+/// shuffling every line after the one being changed.
+pub struct Rope<T> {
+    root: Option<Box<Node<T>>>,
+}
+
+struct Node<T> {
+    value: T,
+    // number of values in the subtree rooted at this node
+    size: uint,
+    height: uint,
+    left: Option<Box<Node<T>>>,
+    right: Option<Box<Node<T>>>,
+}
+
+impl<T> Node<T> {
+    fn new(value: T) -> Node<T> {
+        Node {
+            value: value,
+            size: 1,
+            height: 1,
+            left: None,
+            right: None,
+        }
+    }
+
+    /// Recalculate the size and height after a child has changed
+    fn update(&mut self) {
+        self.size = 1 + size(&self.left) + size(&self.right);
+        self.height = 1 + cmp::max(height(&self.left), height(&self.right));
+    }
+}
+
+impl<T> Rope<T> {
+    /// Create a new empty rope
+    pub fn new() -> Rope<T> {
+        Rope {
+            root: None,
+        }
+    }
+
+    /// Create a new rope holding the values in `values`, in the same order
+    pub fn from_vec(values: Vec<T>) -> Rope<T> {
+        let count = values.len();
+        Rope {
+            root: build(&mut values.into_iter(), count),
+        }
+    }
+
+    /// Get the number of values in the rope
+    pub fn len(&self) -> uint {
+        size(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// Get a reference to the value at `index`
+    pub fn get(&self, index: uint) -> Option<&T> {
+        let mut node = &self.root;
+        let mut index = index;
+        loop {
+            match *node {
+                None => return None,
+                Some(ref n) => {
+                    let left_size = size(&n.left);
+                    if index < left_size {
+                        node = &n.left;
+                    } else if index == left_size {
+                        return Some(&n.value)
+                    } else {
+                        index -= left_size + 1;
+                        node = &n.right;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Get a mutable reference to the value at `index`
+    pub fn get_mut(&mut self, index: uint) -> Option<&mut T> {
+        get_mut(&mut self.root, index)
+    }
+
+    /// Insert `value` at `index`, shifting every value after it along by one.
+    ///
+    /// Panics if `index` is greater than the length of the rope.
+    pub fn insert(&mut self, index: uint, value: T) {
+        assert!(index <= self.len(), "index out of bounds: {} > {}", index, self.len());
+        let root = self.root.take();
+        self.root = Some(insert(root, index, value));
+    }
+
+    /// Add `value` to the end of the rope
+    pub fn push(&mut self, value: T) {
+        let len = self.len();
+        self.insert(len, value);
+    }
+
+    /// Remove and return the value at `index`, shifting every value after it
+    /// back by one. Returns None if `index` is out of bounds.
+    pub fn remove(&mut self, index: uint) -> Option<T> {
+        if index >= self.len() { return None }
+
+        let root = self.root.take().unwrap();
+        let (root, value) = remove(root, index);
+        self.root = root;
+        Some(value)
+    }
+
+    /// Iterate over every value in the rope, in order
+    pub fn iter(&self) -> Iter<T> {
+        self.iter_from(0)
+    }
+
+    /// Iterate over the values in the rope, starting at `index`
+    pub fn iter_from(&self, index: uint) -> Iter<T> {
+        let mut stack = Vec::new();
+        let mut node = &self.root;
+        let mut index = index;
+
+        // keep every node on the way down which comes after the one we want,
+        // the node at `index` ends up at the top of the stack
+        loop {
+            match *node {
+                None => break,
+                Some(ref n) => {
+                    let left_size = size(&n.left);
+                    if index < left_size {
+                        stack.push(&**n);
+                        node = &n.left;
+                    } else if index == left_size {
+                        stack.push(&**n);
+                        break;
+                    } else {
+                        index -= left_size + 1;
+                        node = &n.right;
+                    }
+                }
+            }
+        }
+
+        Iter { stack: stack }
+    }
+}
+
+
+/// An in-order iterator over the values in a Rope
+pub struct Iter<'a, T: 'a> {
+    stack: Vec<&'a Node<T>>,
+}
+
+impl<'a, T> Iterator<&'a T> for Iter<'a, T> {
+    fn next(&mut self) -> Option<&'a T> {
+        let node = match self.stack.pop() {
+            Some(node) => node,
+            None       => return None,
+        };
+
+        // the next value is the leftmost one in the right subtree
+        let mut next = &node.right;
+        while let Some(ref n) = *next {
+            self.stack.push(&**n);
+            next = &n.left;
+        }
+
+        Some(&node.value)
+    }
+}
+
+
+fn size<T>(node: &Option<Box<Node<T>>>) -> uint {
+    match *node {
+        Some(ref n) => n.size,
+        None        => 0,
+    }
+}
+
+fn height<T>(node: &Option<Box<Node<T>>>) -> uint {
+    match *node {
+        Some(ref n) => n.height,
+        None        => 0,
+    }
+}
+
+/// Build a balanced tree from the next `count` values of `values`
+fn build<T, I: Iterator<T>>(values: &mut I, count: uint) -> Option<Box<Node<T>>> {
+    if count == 0 { return None }
+
+    let left_count = count / 2;
+    let left = build(values, left_count);
+    let mut node = box Node::new(values.next().unwrap());
+    node.left = left;
+    node.right = build(values, count - left_count - 1);
+    node.update();
+
+    Some(node)
+}
+
+fn get_mut<T>(node: &mut Option<Box<Node<T>>>, index: uint) -> Option<&mut T> {
+    match *node {
+        None => None,
+        Some(ref mut n) => {
+            let left_size = size(&n.left);
+            if index < left_size {
+                get_mut(&mut n.left, index)
+            } else if index == left_size {
+                Some(&mut n.value)
+            } else {
+                get_mut(&mut n.right, index - left_size - 1)
+            }
+        }
+    }
+}
+
+fn rotate_left<T>(mut node: Box<Node<T>>) -> Box<Node<T>> {
+    let mut right = node.right.take().unwrap();
+    node.right = right.left.take();
+    node.update();
+    right.left = Some(node);
+    right.update();
+    right
+}
+
+fn rotate_right<T>(mut node: Box<Node<T>>) -> Box<Node<T>> {
+    let mut left = node.left.take().unwrap();
+    node.left = left.right.take();
+    node.update();
+    left.right = Some(node);
+    left.update();
+    left
+}
+
+/// Restore the balance of a node whose children differ in height by at most two
+fn balance<T>(mut node: Box<Node<T>>) -> Box<Node<T>> {
+    node.update();
+    let left_height = height(&node.left);
+    let right_height = height(&node.right);
+
+    if left_height > right_height + 1 {
+        let mut left = node.left.take().unwrap();
+        if height(&left.right) > height(&left.left) {
+            left = rotate_left(left);
+        }
+        node.left = Some(left);
+        rotate_right(node)
+    } else if right_height > left_height + 1 {
+        let mut right = node.right.take().unwrap();
+        if height(&right.left) > height(&right.right) {
+            right = rotate_right(right);
+        }
+        node.right = Some(right);
+        rotate_left(node)
+    } else {
+        node
+    }
+}
+
+fn insert<T>(node: Option<Box<Node<T>>>, index: uint, value: T) -> Box<Node<T>> {
+    match node {
+        None => box Node::new(value),
+        Some(mut node) => {
+            let left_size = size(&node.left);
+            if index <= left_size {
+                let left = node.left.take();
+                node.left = Some(insert(left, index, value));
+            } else {
+                let right = node.right.take();
+                node.right = Some(insert(right, index - left_size - 1, value));
+            }
+            balance(node)
+        }
+    }
+}
+
+/// Remove the value at `index` from the subtree, returning what is left of
+/// the subtree along with the value.
+fn remove<T>(mut node: Box<Node<T>>, index: uint) -> (Option<Box<Node<T>>>, T) {
+    let left_size = size(&node.left);
+
+    if index < left_size {
+        let (left, value) = remove(node.left.take().unwrap(), index);
+        node.left = left;
+        return (Some(balance(node)), value)
+    }
+    if index > left_size {
+        let (right, value) = remove(node.right.take().unwrap(), index - left_size - 1);
+        node.right = right;
+        return (Some(balance(node)), value)
+    }
+
+    let Node { value, left, right, .. } = *node;
+    match (left, right) {
+        (None, right) => (right, value),
+        (left, None)  => (left, value),
+        (left, Some(right)) => {
+            // the first node of the right subtree takes the place of this one
+            let (right, mut next) = remove_first(right);
+            next.left = left;
+            next.right = right;
+            (Some(balance(next)), value)
+        }
+    }
+}
+
+/// Detach the first node of the subtree, returning what is left of the
+/// subtree along with the detached node.
+fn remove_first<T>(mut node: Box<Node<T>>) -> (Option<Box<Node<T>>>, Box<Node<T>>) {
+    match node.left.take() {
+        None => {
+            let right = node.right.take();
+            (right, node)
+        }
+        Some(left) => {
+            let (left, first) = remove_first(left);
+            node.left = left;
+            (Some(balance(node)), first)
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use rope::{Rope, height};
+
+    fn setup_rope(count: uint) -> Rope<uint> {
+        Rope::from_vec(range(0, count).collect())
+    }
+
+    fn values(rope: &Rope<uint>) -> Vec<uint> {
+        rope.iter().map(|v| *v).collect()
+    }
+
+    #[test]
+    fn from_vec_keeps_values_in_order() {
+        let rope = setup_rope(10);
+
+        assert_eq!(rope.len(), 10);
+        assert_eq!(values(&rope), range(0, 10).collect());
+    }
+
+    #[test]
+    fn get_finds_values_by_index() {
+        let rope = setup_rope(100);
+
+        assert_eq!(rope.get(0), Some(&0));
+        assert_eq!(rope.get(57), Some(&57));
+        assert_eq!(rope.get(99), Some(&99));
+        assert_eq!(rope.get(100), None);
+    }
+
+    #[test]
+    fn get_mut_changes_a_value() {
+        let mut rope = setup_rope(10);
+        *rope.get_mut(4).unwrap() = 40;
+
+        assert_eq!(rope.get(4), Some(&40));
+    }
+
+    #[test]
+    fn insert_shifts_following_values() {
+        let mut rope = setup_rope(3);
+        rope.insert(1, 10);
+        rope.insert(4, 20);
+        rope.insert(0, 30);
+
+        assert_eq!(values(&rope), vec![30, 0, 10, 1, 2, 20]);
+    }
+
+    #[test]
+    fn remove_returns_the_value() {
+        let mut rope = setup_rope(5);
+
+        assert_eq!(rope.remove(2), Some(2));
+        assert_eq!(rope.remove(0), Some(0));
+        assert_eq!(rope.remove(5), None);
+        assert_eq!(values(&rope), vec![1, 3, 4]);
+    }
+
+    #[test]
+    fn iter_from_starts_at_index() {
+        let rope = setup_rope(20);
+        let from: Vec<uint> = rope.iter_from(13).map(|v| *v).collect();
+
+        assert_eq!(from, range(13, 20).collect());
+        assert_eq!(rope.iter_from(20).next(), None);
+    }
+
+    #[test]
+    fn tree_stays_balanced() {
+        let mut rope = Rope::new();
+        for value in range(0u, 1024) {
+            rope.push(value);
+        }
+        for _ in range(0u, 512) {
+            rope.remove(0);
+        }
+
+        assert_eq!(rope.len(), 512);
+        assert_eq!(rope.get(0), Some(&512));
+        // an AVL tree of 512 values is at most 1.44 * log2(512) high
+        assert!(height(&rope.root) <= 13);
+    }
+}
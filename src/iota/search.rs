@@ -0,0 +1,179 @@
+//! Finding text in a Buffer.
+
+use buffer::Buffer;
+use view::Region;
+
+/// Which way through the buffer a search goes from the cursor
+#[deriving(Clone, PartialEq, Show)]
+pub enum SearchDirection {
+    Forward,
+    Backward,
+}
+
+
+/// How letters in a search pattern match letters in the buffer
+#[deriving(Clone, PartialEq, Show)]
+pub enum CaseMatching {
+    /// Letters only match letters of the same case
+    Sensitive,
+    /// Letters match letters of either case
+    Insensitive,
+    /// Letters match letters of either case, unless the pattern has an upper
+    /// case letter in it
+    Smart,
+}
+
+impl CaseMatching {
+    /// Check whether case should be ignored when searching for `pattern`
+    pub fn ignores_case(&self, pattern: &str) -> bool {
+        match *self {
+            CaseMatching::Sensitive   => false,
+            CaseMatching::Insensitive => true,
+            CaseMatching::Smart       => !pattern.chars().any(|ch| ch.is_uppercase()),
+        }
+    }
+}
+
+
+/// Find the nearest match for `pattern` in `buffer`, going from the
+/// (offset, line_num) position `from` in `direction` and wrapping around at
+/// either end of the buffer.
+///
+/// Searching forward finds a match starting at or after `from`, while
+/// searching backward finds one starting before it.
+pub fn find(buffer: &Buffer, pattern: &str, from: (uint, uint),
+            direction: SearchDirection, ignore_case: bool) -> Option<Region> {
+    if pattern.is_empty() { return None }
+
+    let (from_offset, from_line) = from;
+    let line_count = buffer.line_count();
+
+    // the line the search starts on is visited again at the end, for the
+    // part of it which was skipped the first time
+    for step in range(0, line_count + 1) {
+        let line_num = match direction {
+            SearchDirection::Forward  => (from_line + step) % line_count,
+            SearchDirection::Backward => (from_line + line_count - step % line_count) % line_count,
+        };
+        let matches = find_in_line(buffer.get_line(line_num).data.as_slice(), pattern, ignore_case);
+
+        let found = match direction {
+            SearchDirection::Forward => {
+                let mut candidates = matches.into_iter();
+                if step == 0 {
+                    candidates.find(|&(start, _)| start >= from_offset)
+                } else if step == line_count {
+                    candidates.find(|&(start, _)| start < from_offset)
+                } else {
+                    candidates.next()
+                }
+            }
+            SearchDirection::Backward => {
+                let mut candidates = matches.into_iter().rev();
+                if step == 0 {
+                    candidates.find(|&(start, _)| start < from_offset)
+                } else if step == line_count {
+                    candidates.find(|&(start, _)| start >= from_offset)
+                } else {
+                    candidates.next()
+                }
+            }
+        };
+
+        if let Some((start, end)) = found {
+            return Some(((start, line_num), (end, line_num)))
+        }
+    }
+    None
+}
+
+/// Find every match for `pattern` in `text`, as (start, end) byte offsets
+fn find_in_line(text: &str, pattern: &str, ignore_case: bool) -> Vec<(uint, uint)> {
+    text.char_indices()
+        .filter_map(|(start, _)| match_at(text, start, pattern, ignore_case).map(|end| (start, end)))
+        .collect()
+}
+
+/// Check whether `pattern` matches `text` at the byte offset `start`, and get
+/// the offset just past the match if it does
+fn match_at(text: &str, start: uint, pattern: &str, ignore_case: bool) -> Option<uint> {
+    let rest = text.slice_from(start);
+    let mut chars = rest.char_indices();
+
+    for expected in pattern.chars() {
+        match chars.next() {
+            Some((_, ch)) if same_char(ch, expected, ignore_case) => {}
+            _ => return None,
+        }
+    }
+
+    match chars.next() {
+        Some((index, _)) => Some(start + index),
+        None             => Some(text.len()),
+    }
+}
+
+fn same_char(a: char, b: char, ignore_case: bool) -> bool {
+    if ignore_case {
+        a.to_lowercase() == b.to_lowercase()
+    } else {
+        a == b
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use buffer::{Buffer, Line};
+    use rope::Rope;
+    use search::{CaseMatching, SearchDirection, find};
+    use utils::data_from_str;
+
+    fn setup_buffer() -> Buffer {
+        let mut buffer = Buffer::new();
+        buffer.lines = Rope::from_vec(vec!(
+            Line::new(data_from_str("one Two")),
+            Line::new(data_from_str("three")),
+            Line::new(data_from_str("two, twö")),
+        ));
+        buffer
+    }
+
+    #[test]
+    fn forward_search_finds_the_next_match_and_wraps() {
+        let buffer = setup_buffer();
+
+        assert_eq!(find(&buffer, "tw", (0, 0), SearchDirection::Forward, false), Some(((0, 2), (2, 2))));
+        assert_eq!(find(&buffer, "tw", (0, 2), SearchDirection::Forward, false), Some(((0, 2), (2, 2))));
+        assert_eq!(find(&buffer, "tw", (1, 2), SearchDirection::Forward, false), Some(((5, 2), (7, 2))));
+        assert_eq!(find(&buffer, "tw", (6, 2), SearchDirection::Forward, false), Some(((0, 2), (2, 2))));
+        assert_eq!(find(&buffer, "four", (0, 0), SearchDirection::Forward, false), None);
+        assert_eq!(find(&buffer, "", (0, 0), SearchDirection::Forward, false), None);
+    }
+
+    #[test]
+    fn backward_search_finds_the_previous_match_and_wraps() {
+        let buffer = setup_buffer();
+
+        assert_eq!(find(&buffer, "t", (0, 2), SearchDirection::Backward, false), Some(((0, 1), (1, 1))));
+        assert_eq!(find(&buffer, "tw", (5, 2), SearchDirection::Backward, false), Some(((0, 2), (2, 2))));
+        assert_eq!(find(&buffer, "tw", (0, 2), SearchDirection::Backward, false), Some(((5, 2), (7, 2))));
+    }
+
+    #[test]
+    fn case_can_be_ignored() {
+        let buffer = setup_buffer();
+
+        assert_eq!(find(&buffer, "two", (0, 0), SearchDirection::Forward, true), Some(((4, 0), (7, 0))));
+        assert_eq!(find(&buffer, "TWÖ", (0, 0), SearchDirection::Forward, true), Some(((5, 2), (9, 2))));
+    }
+
+    #[test]
+    fn smart_case_only_ignores_case_for_lower_case_patterns() {
+        assert!(CaseMatching::Smart.ignores_case("two"));
+        assert!(!CaseMatching::Smart.ignores_case("Two"));
+        assert!(CaseMatching::Insensitive.ignores_case("Two"));
+        assert!(!CaseMatching::Sensitive.ignores_case("two"));
+    }
+}
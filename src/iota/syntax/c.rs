@@ -0,0 +1,20 @@
+use syntax::{Grammar, Style};
+use syntax::{delimited, escaped, pattern};
+
+pub fn grammar() -> Grammar {
+    Grammar::new("C", &["c", "h"], vec![
+        delimited(r"/\*", r"\*/",                       Style::Comment),
+        pattern(r"//.*",                                Style::Comment),
+        escaped("\"", "\"", '\\',                       Style::String),
+        pattern(r"'(?:\\.|[^\\'])*'",                   Style::String),
+
+        pattern(r"^\s*#\s*[a-z]+",                      Style::Special),
+
+        pattern(r"\b(?:break|case|continue|default|do|else|enum|extern|for|goto|if|inline|register|return|sizeof|static|struct|switch|typedef|union|volatile|while)\b",
+                                                        Style::Keyword),
+        pattern(r"\b(?:char|const|double|float|int|long|short|signed|unsigned|void|[a-z_][a-z0-9_]*_t)\b",
+                                                        Style::Type),
+        pattern(r"\b(?:NULL|true|false|0x[0-9a-fA-F]+[uUlL]*|[0-9]+(?:\.[0-9]+)?(?:[eE][+-]?[0-9]+)?[uUlLfF]*)\b",
+                                                        Style::Constant),
+    ])
+}
@@ -0,0 +1,495 @@
+//! Syntax highlighting.
+//!
+//! Each language has a Grammar, which lists the rules for picking out parts
+//! of a line such as keywords, strings and comments. The grammars are written
+//! declaratively in their own files, and chosen by the extension of the file
+//! being edited.
+
+extern crate rustbox;
+
+use std::cmp;
+
+use regex::Regex;
+
+use buffer::Line;
+use rope::Rope;
+
+mod c;
+mod python;
+mod rust;
+
+
+/// What a part of a line is, which decides the colour it is drawn in
+#[deriving(Clone, PartialEq, Show)]
+pub enum Style {
+    Normal,
+    Keyword,
+    Type,
+    /// Numbers and other literal values
+    Constant,
+    String,
+    Comment,
+    /// Macros, attributes, preprocessor directives and the like
+    Special,
+}
+
+impl Style {
+    pub fn color(&self) -> rustbox::Color {
+        match *self {
+            Style::Normal   => rustbox::Color::White,
+            Style::Keyword  => rustbox::Color::Yellow,
+            Style::Type     => rustbox::Color::Cyan,
+            Style::Constant => rustbox::Color::Magenta,
+            Style::String   => rustbox::Color::Green,
+            Style::Comment  => rustbox::Color::Blue,
+            Style::Special  => rustbox::Color::Red,
+        }
+    }
+}
+
+
+/// A part of a line to draw in a Style, from the byte offset `start` up to
+/// `end`
+#[deriving(Clone, PartialEq, Show)]
+pub struct Span {
+    pub start: uint,
+    pub end: uint,
+    pub style: Style,
+}
+
+/// Get the style of the text at `offset` in a line with the given spans
+pub fn style_at(spans: &[Span], offset: uint) -> Style {
+    for span in spans.iter() {
+        if offset < span.start { break }
+        if offset < span.end { return span.style.clone() }
+    }
+    Style::Normal
+}
+
+
+/// One way of picking out part of a line
+pub enum Rule {
+    /// Text matching the regex on a single line
+    Pattern(Regex, Style),
+    /// Text from a match for the first regex up to the next match for the
+    /// second, which may be on a later line. A match for the second regex
+    /// straight after the escape char doesn't count.
+    Delimited(Regex, Regex, Option<char>, Style),
+}
+
+/// Create a rule for text matching `pattern`
+pub fn pattern(pattern: &str, style: Style) -> Rule {
+    Rule::Pattern(Regex::new(pattern).unwrap(), style)
+}
+
+/// Create a rule for text between a match for `start` and a match for `end`
+pub fn delimited(start: &str, end: &str, style: Style) -> Rule {
+    Rule::Delimited(Regex::new(start).unwrap(), Regex::new(end).unwrap(), None, style)
+}
+
+/// Create a rule for text between a match for `start` and a match for `end`
+/// which isn't preceded by `escape`
+pub fn escaped(start: &str, end: &str, escape: char, style: Style) -> Rule {
+    Rule::Delimited(Regex::new(start).unwrap(), Regex::new(end).unwrap(), Some(escape), style)
+}
+
+impl Rule {
+    fn style(&self) -> Style {
+        match *self {
+            Rule::Pattern(_, ref style)         => style.clone(),
+            Rule::Delimited(_, _, _, ref style) => style.clone(),
+        }
+    }
+
+    /// Find where the rule first matches `text` at or after `from`
+    fn find_start(&self, text: &str, from: uint) -> Option<(uint, uint)> {
+        let regex = match *self {
+            Rule::Pattern(ref regex, _)         => regex,
+            Rule::Delimited(ref regex, _, _, _) => regex,
+        };
+        regex.find(text.slice_from(from)).map(|(start, end)| (from + start, from + end))
+    }
+
+    /// Find the end of the text picked out by a delimited rule which started
+    /// before `from`, returning the offset just past it
+    fn find_end(&self, text: &str, from: uint) -> Option<uint> {
+        let (regex, escape) = match *self {
+            Rule::Delimited(_, ref regex, escape, _) => (regex, escape),
+            Rule::Pattern(..)                        => return Some(from),
+        };
+
+        let mut pos = from;
+        while pos < text.len() {
+            let (start, end) = match regex.find(text.slice_from(pos)) {
+                Some((start, end)) => (pos + start, pos + end),
+                None               => return None,
+            };
+
+            // the end doesn't count after an odd number of escape chars
+            let escaped = match escape {
+                Some(escape) => {
+                    let escapes = text.slice(from, start).chars().rev().take_while(|&ch| ch == escape).count();
+                    escapes % 2 == 1
+                }
+                None => false,
+            };
+            if !escaped && end > start { return Some(end) }
+
+            if start >= text.len() { break }
+            pos = text.char_range_at(start).next;
+        }
+        None
+    }
+}
+
+
+/// The highlighting state at the end of a line, which is the index of the
+/// delimited rule still open at that point, if any
+pub type State = Option<uint>;
+
+
+/// The rules for highlighting a language
+pub struct Grammar {
+    pub name: &'static str,
+    extensions: Vec<&'static str>,
+    rules: Vec<Rule>,
+}
+
+impl Grammar {
+    /// Create a grammar for files with any of the `extensions`. Where rules
+    /// match at the same place, the first one listed wins.
+    pub fn new(name: &'static str, extensions: &[&'static str], rules: Vec<Rule>) -> Grammar {
+        Grammar {
+            name: name,
+            extensions: extensions.to_vec(),
+            rules: rules,
+        }
+    }
+
+    /// Split `text` into the spans to draw in each style, given the state
+    /// left at the end of the line before. Text which isn't in any span is
+    /// drawn normally.
+    ///
+    /// Returns the spans along with the state at the end of the line.
+    pub fn highlight_line(&self, text: &str, state: State) -> (Vec<Span>, State) {
+        let mut spans = Vec::new();
+        let mut pos = 0;
+
+        // finish off anything left open by the line before
+        if let Some(index) = state {
+            let rule = &self.rules[index];
+            match rule.find_end(text, 0) {
+                Some(end) => {
+                    spans.push(Span { start: 0, end: end, style: rule.style() });
+                    pos = end;
+                }
+                None => {
+                    spans.push(Span { start: 0, end: text.len(), style: rule.style() });
+                    return (spans, state)
+                }
+            }
+        }
+
+        while pos < text.len() {
+            // the rule which matches nearest, ignoring empty matches
+            let mut nearest: Option<(uint, uint, uint)> = None;
+            for (index, rule) in self.rules.iter().enumerate() {
+                if let Some((start, end)) = rule.find_start(text, pos) {
+                    let nearer = nearest.map_or(true, |(_, nearest_start, _)| start < nearest_start);
+                    if end > start && nearer {
+                        nearest = Some((index, start, end));
+                    }
+                }
+            }
+
+            let (index, start, end) = match nearest {
+                Some(found) => found,
+                None        => break,
+            };
+            let rule = &self.rules[index];
+            let span_end = match *rule {
+                Rule::Pattern(..) => end,
+                Rule::Delimited(..) => match rule.find_end(text, end) {
+                    Some(span_end) => span_end,
+                    None => {
+                        spans.push(Span { start: start, end: text.len(), style: rule.style() });
+                        return (spans, Some(index))
+                    }
+                },
+            };
+            spans.push(Span { start: start, end: span_end, style: rule.style() });
+            pos = span_end;
+        }
+
+        (spans, None)
+    }
+}
+
+
+/// Get the grammar for the language of the file at `path`, judging by its
+/// extension
+pub fn grammar_for_path(path: &Path) -> Option<Grammar> {
+    let extension = match path.extension_str() {
+        Some(extension) => extension,
+        None            => return None,
+    };
+    all_grammars().into_iter().find(|grammar| grammar.extensions.iter().any(|&e| e == extension))
+}
+
+fn all_grammars() -> Vec<Grammar> {
+    vec![
+        c::grammar(),
+        python::grammar(),
+        rust::grammar(),
+    ]
+}
+
+
+/// Keeps the highlighting of a Buffer's lines between draws.
+///
+/// Lines are highlighted in order from the start of the buffer, as each one
+/// depends on the state left by the one before. A changed line is worked out
+/// again the next time it is needed, along with the lines after it until one
+/// ends in the same state as it did before, as the rest are unaffected.
+pub struct Highlighter {
+    grammar: Grammar,
+    // the highlighting of each line worked out so far
+    lines: Vec<Highlighted>,
+    // every line before this one is up to date
+    first_stale: uint,
+}
+
+// the spans of a line, with the state at the end of it
+struct Highlighted {
+    spans: Vec<Span>,
+    state: State,
+    // the line has changed, or follows a line which now ends differently
+    stale: bool,
+}
+
+impl Highlighter {
+    pub fn new(grammar: Grammar) -> Highlighter {
+        Highlighter {
+            grammar: grammar,
+            lines: Vec::new(),
+            first_stale: 0,
+        }
+    }
+
+    /// Throw away all the highlighting, after every line has been replaced
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.first_stale = 0;
+    }
+
+    /// Mark line `line_num` to be highlighted again, after it has changed
+    pub fn line_changed(&mut self, line_num: uint) {
+        if line_num < self.lines.len() {
+            self.lines[line_num].stale = true;
+        }
+        self.first_stale = cmp::min(self.first_stale, line_num);
+    }
+
+    /// Make room for a line inserted at `line_num`
+    pub fn line_inserted(&mut self, line_num: uint) {
+        if line_num < self.lines.len() {
+            // until the new line is highlighted, it ends in the state the
+            // line after it was highlighted from
+            let state = if line_num == 0 { None } else { self.lines[line_num - 1].state };
+            self.lines.insert(line_num, Highlighted { spans: Vec::new(), state: state, stale: true });
+        }
+        self.first_stale = cmp::min(self.first_stale, line_num);
+    }
+
+    /// Forget the highlighting of line `line_num`, after it has been removed
+    pub fn line_removed(&mut self, line_num: uint) {
+        if line_num < self.lines.len() {
+            self.lines.remove(line_num);
+        }
+        // the line after it now follows a different one
+        self.line_changed(line_num);
+    }
+
+    /// Get the number of lines whose highlighting is kept
+    pub fn highlighted_lines(&self) -> uint {
+        self.lines.len()
+    }
+
+    /// Get the spans of line `line_num` in `lines`, highlighting it and any
+    /// lines before it which aren't up to date
+    pub fn get_spans(&mut self, lines: &Rope<Line>, line_num: uint) -> Vec<Span> {
+        if line_num >= lines.len() { return Vec::new() }
+
+        let start = self.first_stale;
+        if start <= line_num {
+            for (index, line) in lines.iter_from(start).enumerate() {
+                let current = start + index;
+                if current > line_num { break }
+                if current < self.lines.len() && !self.lines[current].stale { continue }
+
+                let state = if current == 0 { None } else { self.lines[current - 1].state };
+                let (spans, end_state) = self.grammar.highlight_line(line.data.as_slice(), state);
+                let highlighted = Highlighted { spans: spans, state: end_state, stale: false };
+
+                if current < self.lines.len() {
+                    // the next line only needs doing again if this one ends
+                    // differently than it did
+                    let changed = self.lines[current].state != end_state;
+                    self.lines[current] = highlighted;
+                    if changed && current + 1 < self.lines.len() {
+                        self.lines[current + 1].stale = true;
+                    }
+                } else {
+                    self.lines.push(highlighted);
+                }
+            }
+            self.first_stale = line_num + 1;
+        }
+        self.lines[line_num].spans.clone()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use buffer::Line;
+    use rope::Rope;
+    use syntax::{Grammar, Highlighter, Span, Style};
+    use syntax::{all_grammars, delimited, escaped, grammar_for_path, pattern, style_at};
+    use utils::data_from_str;
+
+    fn setup_grammar() -> Grammar {
+        Grammar::new("test", &["test"], vec![
+            delimited(r"/\*", r"\*/", Style::Comment),
+            pattern(r"//.*", Style::Comment),
+            escaped("\"", "\"", '\\', Style::String),
+            pattern(r"\b(?:if|else)\b", Style::Keyword),
+            pattern(r"\b[0-9]+\b", Style::Constant),
+        ])
+    }
+
+    fn span(start: uint, end: uint, style: Style) -> Span {
+        Span { start: start, end: end, style: style }
+    }
+
+    #[test]
+    fn rules_pick_out_parts_of_a_line() {
+        let grammar = setup_grammar();
+        let (spans, state) = grammar.highlight_line("if x == 10 // ten", None);
+
+        assert_eq!(spans, vec![
+            span(0, 2, Style::Keyword),
+            span(8, 10, Style::Constant),
+            span(11, 17, Style::Comment),
+        ]);
+        assert_eq!(state, None);
+        assert_eq!(style_at(spans.as_slice(), 9), Style::Constant);
+        assert_eq!(style_at(spans.as_slice(), 3), Style::Normal);
+    }
+
+    #[test]
+    fn escaped_delimiters_are_skipped() {
+        let grammar = setup_grammar();
+        let (spans, _) = grammar.highlight_line(r#"x = "a \" if \\" else"#, None);
+
+        assert_eq!(spans, vec![
+            span(4, 16, Style::String),
+            span(17, 21, Style::Keyword),
+        ]);
+    }
+
+    #[test]
+    fn delimited_text_carries_across_lines() {
+        let grammar = setup_grammar();
+        let (spans, state) = grammar.highlight_line("1 /* if", None);
+        assert_eq!(spans, vec![span(0, 1, Style::Constant), span(2, 7, Style::Comment)]);
+        assert_eq!(state, Some(0));
+
+        let (spans, state) = grammar.highlight_line("else", state);
+        assert_eq!(spans, vec![span(0, 4, Style::Comment)]);
+
+        let (spans, state) = grammar.highlight_line("*/ else", state);
+        assert_eq!(spans, vec![span(0, 2, Style::Comment), span(3, 7, Style::Keyword)]);
+        assert_eq!(state, None);
+    }
+
+    #[test]
+    fn changed_lines_are_highlighted_again() {
+        let mut lines = Rope::from_vec(vec!(
+            Line::new(data_from_str("/*")),
+            Line::new(data_from_str("if")),
+            Line::new(data_from_str("*/")),
+            Line::new(data_from_str("if")),
+        ));
+        let mut highlighter = Highlighter::new(setup_grammar());
+
+        assert_eq!(highlighter.get_spans(&lines, 1), vec![span(0, 2, Style::Comment)]);
+        assert_eq!(highlighter.highlighted_lines(), 2);
+
+        lines.get_mut(0).unwrap().data = data_from_str("x");
+        highlighter.line_changed(0);
+        assert_eq!(highlighter.get_spans(&lines, 1), vec![span(0, 2, Style::Keyword)]);
+        assert_eq!(highlighter.get_spans(&lines, 3), vec![span(0, 2, Style::Keyword)]);
+        assert_eq!(highlighter.highlighted_lines(), 4);
+    }
+
+    #[test]
+    fn lines_after_an_edit_are_kept_once_the_state_matches() {
+        let mut lines = Rope::from_vec(vec!(
+            Line::new(data_from_str("1")),
+            Line::new(data_from_str("/* 2")),
+            Line::new(data_from_str("3 */")),
+            Line::new(data_from_str("4")),
+        ));
+        let mut highlighter = Highlighter::new(setup_grammar());
+        assert_eq!(highlighter.get_spans(&lines, 3), vec![span(0, 1, Style::Constant)]);
+
+        // the last line is changed behind the highlighter's back, so its old
+        // spans show whether it was highlighted again
+        lines.get_mut(3).unwrap().data = data_from_str("if");
+        lines.get_mut(1).unwrap().data = data_from_str("/* 20");
+        highlighter.line_changed(1);
+        assert_eq!(highlighter.get_spans(&lines, 1), vec![span(0, 5, Style::Comment)]);
+        assert_eq!(highlighter.get_spans(&lines, 3), vec![span(0, 1, Style::Constant)]);
+
+        // closing the comment changes the state, so the next line is done
+        // again, but it ends as it did before and the one after is kept
+        lines.get_mut(1).unwrap().data = data_from_str("/* 2 */");
+        highlighter.line_changed(1);
+        assert_eq!(highlighter.get_spans(&lines, 2), vec![span(0, 1, Style::Constant)]);
+        assert_eq!(highlighter.get_spans(&lines, 3), vec![span(0, 1, Style::Constant)]);
+    }
+
+    #[test]
+    fn inserted_and_removed_lines_are_tracked() {
+        let mut lines = Rope::from_vec(vec!(
+            Line::new(data_from_str("/*")),
+            Line::new(data_from_str("*/")),
+            Line::new(data_from_str("if")),
+        ));
+        let mut highlighter = Highlighter::new(setup_grammar());
+        assert_eq!(highlighter.get_spans(&lines, 2), vec![span(0, 2, Style::Keyword)]);
+
+        lines.insert(1, Line::new(data_from_str("if")));
+        highlighter.line_inserted(1);
+        assert_eq!(highlighter.get_spans(&lines, 1), vec![span(0, 2, Style::Comment)]);
+        assert_eq!(highlighter.get_spans(&lines, 3), vec![span(0, 2, Style::Keyword)]);
+        assert_eq!(highlighter.highlighted_lines(), 4);
+
+        lines.remove(0);
+        highlighter.line_removed(0);
+        assert_eq!(highlighter.get_spans(&lines, 0), vec![span(0, 2, Style::Keyword)]);
+        assert!(highlighter.get_spans(&lines, 1).is_empty());
+        assert_eq!(highlighter.highlighted_lines(), 3);
+    }
+
+    #[test]
+    fn grammars_are_chosen_by_extension() {
+        assert_eq!(grammar_for_path(&Path::new("src/main.rs")).unwrap().name, "Rust");
+        assert_eq!(grammar_for_path(&Path::new("setup.py")).unwrap().name, "Python");
+        assert_eq!(grammar_for_path(&Path::new("test.h")).unwrap().name, "C");
+        assert!(grammar_for_path(&Path::new("README")).is_none());
+        assert_eq!(all_grammars().len(), 3);
+    }
+}
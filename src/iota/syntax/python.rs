@@ -0,0 +1,21 @@
+use syntax::{Grammar, Style};
+use syntax::{escaped, pattern};
+
+pub fn grammar() -> Grammar {
+    Grammar::new("Python", &["py"], vec![
+        pattern(r"#.*",                                 Style::Comment),
+        escaped(r#"[bBrRuU]?""""#, r#"""""#, '\\',      Style::String),
+        escaped(r"[bBrRuU]?'''", r"'''", '\\',          Style::String),
+        escaped(r#"[bBrRuU]?""#, "\"", '\\',            Style::String),
+        escaped(r"[bBrRuU]?'", "'", '\\',               Style::String),
+
+        pattern(r"@[A-Za-z_][A-Za-z0-9_.]*",            Style::Special),
+
+        pattern(r"\b(?:and|as|assert|break|class|continue|def|del|elif|else|except|finally|for|from|global|if|import|in|is|lambda|nonlocal|not|or|pass|raise|return|try|while|with|yield)\b",
+                                                        Style::Keyword),
+        pattern(r"\b(?:bool|bytes|dict|float|int|list|object|set|str|tuple)\b",
+                                                        Style::Type),
+        pattern(r"\b(?:True|False|None|self|0[xX][0-9a-fA-F]+|[0-9]+(?:\.[0-9]+)?(?:[eE][+-]?[0-9]+)?[jJ]?)\b",
+                                                        Style::Constant),
+    ])
+}
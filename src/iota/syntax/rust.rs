@@ -0,0 +1,21 @@
+use syntax::{Grammar, Style};
+use syntax::{delimited, escaped, pattern};
+
+pub fn grammar() -> Grammar {
+    Grammar::new("Rust", &["rs"], vec![
+        delimited(r"/\*", r"\*/",                       Style::Comment),
+        pattern(r"//.*",                                Style::Comment),
+        escaped(r#"b?""#, "\"", '\\',                   Style::String),
+        pattern(r"b?'(?:\\.|[^\\'])'",                  Style::String),
+
+        pattern(r"#!?\[[^\]]*\]",                       Style::Special),
+        pattern(r"\b[a-z_][a-z0-9_]*!",                 Style::Special),
+
+        pattern(r"\b(?:as|box|break|const|continue|crate|else|enum|extern|fn|for|if|impl|in|let|loop|match|mod|move|mut|priv|proc|pub|ref|return|self|static|struct|super|trait|type|unsafe|use|where|while)\b",
+                                                        Style::Keyword),
+        pattern(r"\b(?:[A-Z][A-Za-z0-9_]*|bool|char|str|int|uint|i8|i16|i32|i64|u8|u16|u32|u64|f32|f64)\b",
+                                                        Style::Type),
+        pattern(r"\b(?:true|false|0x[0-9a-fA-F_]+|[0-9][0-9_]*(?:\.[0-9_]+)?(?:[eE][+-]?[0-9]+)?(?:[iuf](?:8|16|32|64)?)?)\b",
+                                                        Style::Constant),
+    ])
+}
@@ -0,0 +1,78 @@
+//! Events read from the terminal.
+//!
+//! rustbox only passes on the key and resize events termbox reports, so
+//! events are read from termbox directly here, which rustbox links in, along
+//! with the mouse events it reports once asked to.
+//!
+//! `RawEvent` and the constants below follow `struct tb_event` and the
+//! `TB_INPUT_*` and `TB_EVENT_*` defines in termbox.h. They were checked
+//! against the bindings termbox-sys 0.2.12 makes of that header, not against
+//! the termbox revision the pinned rustbox builds, so they should be checked
+//! again whenever rustbox is updated.
+
+// termbox's input modes
+const TB_INPUT_ESC: i32 = 1;
+const TB_INPUT_MOUSE: i32 = 4;
+
+// the types of termbox's events
+const TB_EVENT_KEY: i32 = 1;
+const TB_EVENT_RESIZE: i32 = 2;
+const TB_EVENT_MOUSE: i32 = 3;
+
+// termbox's struct tb_event
+#[repr(C)]
+struct RawEvent {
+    event_type: u8,
+    modifier: u8,
+    key: u16,
+    ch: u32,
+    width: i32,
+    height: i32,
+    x: i32,
+    y: i32,
+}
+
+extern "C" {
+    fn tb_select_input_mode(mode: i32) -> i32;
+    fn tb_poll_event(event: *mut RawEvent) -> i32;
+}
+
+
+/// Something which happened in the terminal
+#[deriving(Clone, PartialEq, Show)]
+pub enum Event {
+    /// A key pressed, with the char it types if it isn't a special key
+    Key(u16, u32),
+    /// A mouse button pressed or released, or the wheel turned, at (x, y)
+    Mouse(u16, uint, uint),
+    /// The terminal changed to the given width and height
+    Resize(uint, uint),
+}
+
+/// Have the terminal report clicks and the mouse wheel, as well as keys
+pub fn enable_mouse() {
+    unsafe { tb_select_input_mode(TB_INPUT_ESC | TB_INPUT_MOUSE); }
+}
+
+/// Wait for the next event in the terminal, returning None if it couldn't
+/// be read
+pub fn poll_event() -> Option<Event> {
+    let mut raw = RawEvent {
+        event_type: 0,
+        modifier: 0,
+        key: 0,
+        ch: 0,
+        width: 0,
+        height: 0,
+        x: 0,
+        y: 0,
+    };
+    let event_type = unsafe { tb_poll_event(&mut raw) };
+
+    match event_type {
+        TB_EVENT_KEY    => Some(Event::Key(raw.key, raw.ch)),
+        TB_EVENT_MOUSE  => Some(Event::Mouse(raw.key, raw.x as uint, raw.y as uint)),
+        TB_EVENT_RESIZE => Some(Event::Resize(raw.width as uint, raw.height as uint)),
+        _               => None,
+    }
+}
@@ -1,14 +1,76 @@
 extern crate rustbox;
 
 use std::cell::RefCell;
+use std::cmp;
+use std::num::Int;
+use std::rc::Rc;
 
-use buffer::{Line, Buffer};
-use cursor::Direction;
+use buffer;
+use buffer::{Line, Buffer, Change, Operation};
+use cursor::{Direction, Motion};
 use cursor::Cursor;
-use input::Input;
+use options::Options;
+use syntax;
+use syntax::{Span, Style};
 use uibuf::UIBuffer;
 
-use utils;
+/// A range of text from one (offset, line_num) position up to, but not
+/// including, another
+pub type Region = ((uint, uint), (uint, uint));
+
+/// How the gutter down the left of a View numbers the lines
+#[deriving(Clone, PartialEq, Show)]
+pub enum LineNumbers {
+    Off,
+    /// Every line shows its own number, counting from 1
+    Absolute,
+    /// Every line shows how far it is from the cursor line
+    Relative,
+    /// The cursor line shows its own number, and the others how far they
+    /// are from it
+    Hybrid,
+}
+
+impl LineNumbers {
+    /// Get the style with the given name, as given on the command line
+    pub fn from_name(name: &str) -> Option<LineNumbers> {
+        match name {
+            "off"      => Some(LineNumbers::Off),
+            "absolute" => Some(LineNumbers::Absolute),
+            "relative" => Some(LineNumbers::Relative),
+            "hybrid"   => Some(LineNumbers::Hybrid),
+            _          => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            LineNumbers::Off      => "off",
+            LineNumbers::Absolute => "absolute",
+            LineNumbers::Relative => "relative",
+            LineNumbers::Hybrid   => "hybrid",
+        }
+    }
+
+    /// Get the number to show beside line `line_num`, when the cursor is on
+    /// line `cursor_line`
+    pub fn number(&self, line_num: uint, cursor_line: uint) -> uint {
+        let distance = if line_num > cursor_line {
+            line_num - cursor_line
+        } else {
+            cursor_line - line_num
+        };
+        match *self {
+            LineNumbers::Relative               => distance,
+            LineNumbers::Hybrid if distance > 0 => distance,
+            _                                   => line_num + 1,
+        }
+    }
+}
+
+// the fewest columns given to line numbers, so that the gutter doesn't
+// change width while a small file grows
+const MIN_NUMBER_WIDTH: uint = 3;
 
 /// A View is an abstract Window (into a Buffer).
 ///
@@ -16,147 +78,716 @@ use utils;
 /// screen. It maintains the status bar for the current view, the "dirty status"
 /// which is whether the buffer has been modified or not and a number of other
 /// pieces of information.
-pub struct View<'v> {
-    pub buffer: Buffer,
+///
+/// Several Views can share the same Buffer, each with its own cursor.
+#[deriving(Clone)]
+pub struct View {
+    pub buffer: Rc<RefCell<Buffer>>,
     // the line number of the topmost `Line` for the View to render
     pub top_line_num: uint,
-    pub cursor: Cursor<'v>,
-
-    uibuf: UIBuffer,
+    // the display column at the left edge of the View
+    pub left_col: uint,
+    pub cursor: Cursor,
+
+    // the (offset, line_num) position where the selection started, the
+    // cursor being its other end
+    mark: Option<(uint, uint)>,
+    // whether there is a selection while the cursor is still at the mark,
+    // as in visual mode, where it covers the grapheme under the cursor
+    select_at_mark: bool,
+    // text to pick out when there is no selection, such as a search match
+    highlight: Option<Region>,
+
+    options: Options,
+
+    // size of the area the View draws to, including the status line
+    width: uint,
+    height: uint,
     threshold: int,
 }
 
-impl<'v> View<'v> {
-    pub fn new(source: Input) -> View<'v> {
-        let buffer = match source {
-            Input::Filename(path) => {
-                match path {
-                    Some(s) => Buffer::new_from_file(&Path::new(s)),
-                    None    => Buffer::new_empty(),
-                }
-            },
-                Input::Stdin(reader) => {
-                Buffer::new_from_reader(reader)
-            },
-        };
-
-        let height: uint = utils::get_term_height();
-        let width: uint = utils::get_term_width();
-
-        // NOTE(greg): this may not play well with resizing
-        let uibuf = UIBuffer::new(width, height);
-
+impl View {
+    pub fn new(buffer: Rc<RefCell<Buffer>>, options: Options, width: uint, height: uint) -> View {
         let mut cursor = Cursor::new();
-        cursor.set_line(Some(&buffer.lines[0]));
+        cursor.set_line(0, &*buffer.borrow());
 
         View {
             buffer: buffer,
             top_line_num: 0,
+            left_col: 0,
             cursor: cursor,
-            uibuf: uibuf,
+            mark: None,
+            select_at_mark: false,
+            highlight: None,
+            options: options,
+            width: width,
+            height: height,
             threshold: 5,
         }
     }
 
-    /// Clear the buffer
-    ///
-    /// Fills every cell in the UIBuffer with the space (' ') char.
-    pub fn clear(&mut self) {
-        self.uibuf.fill(' ');
-        self.uibuf.draw_everything();
-    }
-
     pub fn get_height(&self) -> uint {
         // NOTE(greg): when the status bar needs to move up, this value should be changed
-        self.uibuf.get_height() -1
+        self.height.saturating_sub(1)
     }
 
     pub fn get_width(&self) -> uint {
-        self.uibuf.get_width()
+        self.width
+    }
+
+    /// Get the width of the gutter down the left of the View, which holds the
+    /// line numbers and marks the rows continuing a wrapped line. The numbers
+    /// are given enough columns for the last line of the buffer, and are
+    /// followed by a blank column.
+    pub fn get_gutter_width(&self) -> uint {
+        if self.options.line_numbers != LineNumbers::Off {
+            let line_count = self.buffer.borrow().line_count();
+            cmp::max(line_count.to_string().len(), MIN_NUMBER_WIDTH) + 1
+        } else if self.options.wrap {
+            1
+        } else {
+            0
+        }
+    }
+
+    /// Get the number of columns beside the gutter which text is drawn in
+    fn get_text_width(&self) -> uint {
+        let gutter_width = self.get_gutter_width();
+        if self.width > gutter_width { self.width - gutter_width } else { 0 }
+    }
+
+    /// Set the size of the area the View draws to
+    pub fn resize(&mut self, width: uint, height: uint) {
+        self.width = width;
+        self.height = height;
+        self.scroll_to_cursor();
+    }
+
+    /// Make sure the cursor and top line still fall within the buffer.
+    ///
+    /// The buffer may have been changed through another View since this one
+    /// was last drawn.
+    pub fn clamp_cursor(&mut self) {
+        let num_lines = self.buffer.borrow().line_count();
+        let linenum = self.cursor.get_linenum();
+        if linenum >= num_lines {
+            self.set_cursor_line(num_lines - 1);
+        } else {
+            self.set_cursor_line(linenum);
+        }
+
+        let linenum = self.cursor.get_linenum();
+        if self.top_line_num > linenum {
+            self.top_line_num = linenum;
+        }
+
+        if let Some((offset, line_num)) = self.mark {
+            let buffer = self.buffer.borrow();
+            self.mark = if line_num < num_lines {
+                Some((buffer.get_line(line_num).grapheme_boundary_at(offset), line_num))
+            } else {
+                None
+            };
+        }
+        self.scroll_to_cursor();
+    }
+
+    /// Start a selection at the cursor, which moving the cursor extends.
+    /// There is no selection until the cursor moves away from the mark.
+    pub fn set_mark(&mut self) {
+        self.mark = Some(self.cursor.get_position());
+        self.select_at_mark = false;
+    }
+
+    /// Start a selection at the cursor which covers the grapheme under it
+    /// straight away, as visual mode does
+    pub fn set_visual_mark(&mut self) {
+        self.mark = Some(self.cursor.get_position());
+        self.select_at_mark = true;
+    }
+
+    /// Check whether a mark has been set, even if nothing is selected yet
+    pub fn has_mark(&self) -> bool {
+        self.mark.is_some()
+    }
+
+    pub fn clear_mark(&mut self) {
+        self.mark = None;
+    }
+
+    /// Pick out `region` in the same way as the selection, while there isn't
+    /// one
+    pub fn set_highlight(&mut self, region: Option<Region>) {
+        self.highlight = region;
+    }
+
+    /// Get the selected region of the buffer, if there is one.
+    ///
+    /// The selection runs from whichever of the mark and the cursor comes
+    /// first, up to and including the grapheme at the other. If that is at
+    /// the end of a line, the line break is included instead.
+    ///
+    /// A mark set with `set_mark` selects nothing while the cursor is on it.
+    pub fn get_selection(&self) -> Option<Region> {
+        let mark = match self.mark {
+            Some(mark) => mark,
+            None       => return None,
+        };
+        let cursor = self.cursor.get_position();
+        if cursor == mark && !self.select_at_mark { return None }
+
+        let (start, (end_offset, end_line)) = if before(mark, cursor) {
+            (mark, cursor)
+        } else {
+            (cursor, mark)
+        };
+
+        let buffer = self.buffer.borrow();
+        let line = buffer.get_line(end_line);
+        let end = if end_offset < line.len() {
+            (line.next_grapheme_boundary(end_offset), end_line)
+        } else if end_line + 1 < buffer.line_count() {
+            (0, end_line + 1)
+        } else {
+            (end_offset, end_line)
+        };
+
+        Some((start, end))
     }
 
-    pub fn draw(&mut self) {
+    /// Delete the selected text, leaving the cursor where it started
+    pub fn delete_selection(&mut self) {
+        let (start, end) = match self.get_selection() {
+            Some(selection) => selection,
+            None            => return,
+        };
+        let cursor_before = self.cursor.get_position();
+        let removed = self.buffer.borrow_mut().remove_text(start, end);
+
+        self.mark = None;
+        let (offset, line_num) = start;
+        self.move_cursor_to(offset, line_num);
+        self.record(Change::Delete(removed), start, cursor_before);
+    }
+
+    /// Delete the line the cursor is on along with its line break, leaving
+    /// the cursor at the start of the line which takes its place
+    pub fn delete_line(&mut self) {
+        let cursor_before = self.cursor.get_position();
+        let (_, line_num) = cursor_before;
+
+        let (start, end, line_count) = {
+            let buffer = self.buffer.borrow();
+            let line_count = buffer.line_count();
+            let line_len = buffer.get_line(line_num).len();
+            if line_num + 1 < line_count {
+                ((0, line_num), (0, line_num + 1), line_count - 1)
+            } else if line_num > 0 {
+                // the last line has no break of its own, so take the one before it
+                let prev_len = buffer.get_line(line_num - 1).len();
+                ((prev_len, line_num - 1), (line_len, line_num), line_count - 1)
+            } else {
+                ((0, line_num), (line_len, line_num), line_count)
+            }
+        };
+        let removed = self.buffer.borrow_mut().remove_text(start, end);
+
+        self.mark = None;
+        self.move_cursor_to(0, cmp::min(line_num, line_count - 1));
+        self.record(Change::Delete(removed), start, cursor_before);
+    }
+
+    /// Get the selected text, if anything is selected
+    pub fn get_selected_text(&self) -> Option<String> {
+        let (start, end) = match self.get_selection() {
+            Some(selection) => selection,
+            None            => return None,
+        };
+        Some(self.buffer.borrow().get_text(start, end))
+    }
+
+    /// Get the text of the line the cursor is on, followed by a line break
+    pub fn get_cursor_line_text(&self) -> String {
+        let buffer = self.buffer.borrow();
+        let mut text = buffer.get_line(self.cursor.get_linenum()).data.clone();
+        text.push('\n');
+        text
+    }
+
+    /// Insert `text` at the cursor, leaving the cursor just past it. Text
+    /// which is `linewise`, being whole lines, goes above the cursor line
+    /// instead, whichever column the cursor is in.
+    ///
+    /// Returns the region taken up by the text.
+    pub fn paste(&mut self, text: &str, linewise: bool) -> Region {
+        let (offset, line_num) = self.cursor.get_position();
+        let start = if linewise { (0, line_num) } else { (offset, line_num) };
+        self.replace_region((start, start), text)
+    }
+
+    /// Put `text` in place of the text in `region`, as a single change, and
+    /// leave the cursor just past it.
+    ///
+    /// Returns the region taken up by the new text.
+    pub fn replace_region(&mut self, region: Region, text: &str) -> Region {
+        let (start, end) = region;
+        let cursor_before = self.cursor.get_position();
+
+        let (change, text_end) = if start == end {
+            let (offset, line_num) = start;
+            let text_end = self.buffer.borrow_mut().insert_text(offset, line_num, text);
+            (Change::Insert(String::from_str(text)), text_end)
+        } else {
+            let (removed, text_end) = self.buffer.borrow_mut().replace_text(start, end, text);
+            (Change::Replace(removed, String::from_str(text)), text_end)
+        };
+
+        let (end_offset, end_line) = text_end;
+        self.move_cursor_to(end_offset, end_line);
+        self.record(change, start, cursor_before);
+        (start, text_end)
+    }
+
+    /// Put `text` in place of the pasted text in `region`, where `paste`
+    /// would have put it. Text which is `linewise` goes at the start of the
+    /// region's first line, so any text before the region on that line ends
+    /// up after it.
+    ///
+    /// Returns the region taken up by the new text.
+    pub fn replace_paste(&mut self, region: Region, text: &str, linewise: bool) -> Region {
+        let ((offset, line_num), end) = region;
+        if !linewise || offset == 0 {
+            return self.replace_region(region, text)
+        }
+
+        // the start of the line is moved after the text in the same change,
+        // so that a single undo puts everything back
+        let before = self.buffer.borrow().get_text((0, line_num), (offset, line_num));
+        let mut replacement = String::from_str(text);
+        replacement.push_str(before.as_slice());
+        let (start, (end_offset, end_line)) = self.replace_region(((0, line_num), end), replacement.as_slice());
+
+        let text_end = (end_offset - before.len(), end_line);
+        self.move_cursor_to(end_offset - before.len(), end_line);
+        (start, text_end)
+    }
+
+    /// Indent the lines touched by the selection, or the cursor line if
+    /// nothing is selected, by one tab stop. Empty lines are left as they are.
+    pub fn indent_lines(&mut self) {
+        self.change_indent(true);
+    }
+
+    /// Take one tab stop of indentation off the lines touched by the
+    /// selection, or the cursor line if nothing is selected
+    pub fn dedent_lines(&mut self) {
+        self.change_indent(false);
+    }
+
+    /// Indent or dedent the selected lines, as a single change which can be
+    /// undone in one go
+    fn change_indent(&mut self, indent: bool) {
+        let (first, last) = self.get_selected_lines();
+        let tab_width = self.options.tab_width;
+        let cursor_before = self.cursor.get_position();
+        let (cursor_offset, cursor_line) = cursor_before;
+
+        let (end, old_len, changed) = {
+            let buffer = self.buffer.borrow();
+            let mut lines = Vec::new();
+            let mut unchanged = true;
+            for line in buffer.lines_from(first).take(last - first + 1) {
+                let text = change_line_indent(line.data.as_slice(), tab_width, indent);
+                unchanged = unchanged && text == line.data;
+                lines.push(text);
+            }
+            if unchanged { return }
+
+            let end = (buffer.get_line(last).len(), last);
+            let old_len = buffer.get_line(cursor_line).len();
+            (end, old_len, lines.connect("\n"))
+        };
+
+        let start = (0, first);
+        let (removed, _) = self.buffer.borrow_mut().replace_text(start, end, changed.as_slice());
+
+        // keep the cursor by the same text it was next to
+        let new_len = self.buffer.borrow().get_line(cursor_line).len();
+        let offset = if new_len > old_len {
+            cursor_offset + new_len - old_len
+        } else if cursor_offset > old_len - new_len {
+            cursor_offset - (old_len - new_len)
+        } else {
+            0
+        };
+
+        self.move_cursor_to(offset, cursor_line);
+        self.record(Change::Replace(removed, changed), start, cursor_before);
+    }
+
+    /// Get the first and last lines touched by the selection, or the cursor
+    /// line twice if nothing is selected
+    fn get_selected_lines(&self) -> (uint, uint) {
+        match self.get_selection() {
+            // a selection ending with a line break doesn't reach the line after
+            Some(((_, first), (0, end_line))) if end_line > first => (first, end_line - 1),
+            Some(((_, first), (_, end_line)))                     => (first, end_line),
+            None => {
+                let line_num = self.cursor.get_linenum();
+                (line_num, line_num)
+            }
+        }
+    }
+
+    /// Switch to the next style of line numbers, returning the new style
+    pub fn cycle_line_numbers(&mut self) -> LineNumbers {
+        let line_numbers = match self.options.line_numbers {
+            LineNumbers::Off      => LineNumbers::Absolute,
+            LineNumbers::Absolute => LineNumbers::Relative,
+            LineNumbers::Relative => LineNumbers::Hybrid,
+            LineNumbers::Hybrid   => LineNumbers::Off,
+        };
+        self.set_line_numbers(line_numbers.clone());
+        line_numbers
+    }
+
+    pub fn set_line_numbers(&mut self, line_numbers: LineNumbers) {
+        self.options.line_numbers = line_numbers;
+        self.scroll_to_cursor();
+    }
+
+    /// Turn soft wrapping of long lines on or off
+    pub fn toggle_wrap(&mut self) {
+        self.options.wrap = !self.options.wrap;
+        self.left_col = 0;
+        self.scroll_to_cursor();
+    }
+
+    /// Draw the visible lines of the buffer to `uibuf`, with the top left
+    /// corner of the View at (`x`, `y`).
+    pub fn draw(&self, uibuf: &mut UIBuffer, x: uint, y: uint) {
         let end_line = self.get_height();
-        let num_lines = self.buffer.lines.len();
-        // TODO(greg): remove the clone from this line - it seems dirty
-        let lines_to_draw = self.buffer.lines.slice(self.top_line_num, num_lines).clone();
+        let buffer = self.buffer.borrow();
+        let selection = self.get_selection().or(self.highlight);
+        let gutter_width = self.get_gutter_width();
+        // a View narrower than its gutter has no room for text, and only
+        // shows the line numbers which fit
+        let number_width = cmp::min(gutter_width.saturating_sub(1), self.width);
+        let has_text = self.get_text_width() > 0;
+
+        let mut row = 0;
+        for (index, line) in buffer.lines_from(self.top_line_num).enumerate() {
+            if row >= end_line { break }
+
+            let line_num = self.top_line_num + index;
+            if self.options.line_numbers != LineNumbers::Off {
+                self.draw_line_number(uibuf, x, y + row, number_width, line_num);
+            }
+            if !has_text {
+                row += 1;
+                continue
+            }
 
-        for (index, line) in lines_to_draw.iter().enumerate() {
-            if index < end_line {
-                self.draw_line(line)
+            let spans = buffer.get_highlighting(line_num);
+            let text_x = x + gutter_width;
+            if self.options.wrap {
+                row += self.draw_wrapped_line(uibuf, text_x, y + row, end_line - row, line, line_num,
+                                              spans.as_slice(), selection);
+            } else {
+                self.draw_line(uibuf, text_x, y + row, line, line_num, spans.as_slice(), selection);
+                row += 1;
             }
         }
+    }
 
-        self.uibuf.draw_everything();
+    /// Draw the number of line `line_num` right aligned in the first `width`
+    /// columns of the row at `y`
+    fn draw_line_number(&self, uibuf: &mut UIBuffer, x: uint, y: uint, width: uint, line_num: uint) {
+        let cursor_line = self.cursor.get_linenum();
+        let number = self.options.line_numbers.number(line_num, cursor_line).to_string();
+        if number.len() > width { return }
+
+        let start = x + width - number.len();
+        for (index, ch) in number.chars().enumerate() {
+            uibuf.update_cell_content(start + index, y, ch);
+        }
     }
 
-    pub fn draw_line(&mut self, line: &'v RefCell<Line>) {
-        let width = self.get_width() -1;
-        let index = line.borrow().linenum;
-        let mut internal_index = 0;
-        for ch in line.borrow().data.iter() {
+    /// Draw a line across as many rows as it needs, up to `max_rows`, with
+    /// the text starting at column `x`.
+    ///
+    /// The column to the left of `x` is the last column of the gutter, which
+    /// marks the rows continuing the line from the row above. Returns the
+    /// number of rows drawn.
+    fn draw_wrapped_line(&self, uibuf: &mut UIBuffer, x: uint, y: uint, max_rows: uint,
+                         line: &Line, line_num: uint, spans: &[Span],
+                         selection: Option<Region>) -> uint {
+        let width = self.get_text_width();
+        let tab_width = self.options.tab_width;
+        let points = line.wrap_points(width, tab_width);
+
+        for (row, &start) in points.iter().enumerate() {
+            if row == max_rows { return row }
+            if row > 0 {
+                uibuf.update_cell_content(x - 1, y + row, '↪');
+            }
 
-            if internal_index < width {
-                let ch = *ch as char;
-                match ch {
-                    '\t' => {
-                        // todo(greg): draw four spaces
-                        // need to update how the cursor is rendered before doing this
-                        // think about what will happen if the cursor sees a single '\t' char
-                        // in the line, however this is represented as four chars to the user
-                        // the actual cursor position will become separated from the drawn
-                        // cursor position.
-                        rustbox::shutdown();
-                        panic!("found tab chars - can't process these right now")
-                    }
-                    _ => {
-                        // draw the character
-                        self.uibuf.update_cell_content(internal_index, index, ch);
+            let end = if row + 1 < points.len() { points[row + 1] } else { line.len() };
+            let mut column = 0;
+            for (index, grapheme) in line.data.as_slice().slice(start, end).grapheme_indices(true) {
+                let start_col = column;
+                column += buffer::display_width(grapheme, start_col, tab_width);
+
+                // a single grapheme can only be wider than the view if the
+                // view is very narrow, in which case it isn't drawn
+                if column > width { break }
+
+                let ch = grapheme.char_at(0);
+                let highlight = in_region(selection, (start + index, line_num));
+                let style = syntax::style_at(spans, start + index);
+                if ch == '\t' {
+                    for col in range(start_col, column) {
+                        draw_cell(uibuf, x + col, y + row, ' ', highlight, style.clone());
                     }
+                } else {
+                    draw_cell(uibuf, x + start_col, y + row, ch, highlight, style);
                 }
-                internal_index += 1;
             }
 
+            // show when the line break is selected too
+            let line_break = (line.len(), line_num);
+            if row + 1 == points.len() && column < width && in_region(selection, line_break) {
+                draw_cell(uibuf, x + column, y + row, ' ', true, Style::Normal);
+            }
+        }
+
+        points.len()
+    }
+
+    pub fn draw_line(&self, uibuf: &mut UIBuffer, x: uint, y: uint, line: &Line,
+                     line_num: uint, spans: &[Span], selection: Option<Region>) {
+        // the last column is kept for the marker shown when a line continues
+        // past the edge of the view
+        let width = self.get_text_width().saturating_sub(1);
+        let tab_width = self.options.tab_width;
+        let mut column = 0;
+        for (offset, grapheme) in line.data.as_slice().grapheme_indices(true) {
+            let start = column;
+            column += buffer::display_width(grapheme, start, tab_width);
+
+            // skip anything scrolled off the left of the view
+            if column <= self.left_col { continue }
+
             // if the line is longer than the width of the view, draw a special char
-            if internal_index == width {
-                // fixme(greg): iota cant render this line correctly right now
-                self.uibuf.update_cell_content(internal_index, index, '→');
-                break;
+            if column > self.left_col + width {
+                uibuf.update_cell_content(x + width, y, '→');
+                return
             }
+
+            // a cell can only hold a single char, so combining marks are dropped
+            let ch = grapheme.char_at(0);
+            let highlight = in_region(selection, (offset, line_num));
+            let style = syntax::style_at(spans, offset);
+            if ch == '\t' || start < self.left_col {
+                // tabs are drawn as spaces up to the next tab stop, as are wide
+                // chars which are cut off by the left of the view
+                for col in range(cmp::max(start, self.left_col), column) {
+                    draw_cell(uibuf, x + col - self.left_col, y, ' ', highlight, style.clone());
+                }
+            } else {
+                // draw the character, the terminal skips the cells covered by a
+                // wide char
+                draw_cell(uibuf, x + start - self.left_col, y, ch, highlight, style);
+            }
+        }
+
+        // show when the line break is selected too
+        let line_break = (line.len(), line_num);
+        let visible = column >= self.left_col && column < self.left_col + width;
+        if visible && in_region(selection, line_break) {
+            draw_cell(uibuf, x + column - self.left_col, y, ' ', true, Style::Normal);
         }
     }
 
-    pub fn draw_status(&mut self) {
-        let buffer_status = self.buffer.get_status_text();
+    /// Draw the status line along the bottom of the View.
+    ///
+    /// The status line of the focused View is drawn in a different colour, and
+    /// starts with the name of the editing `mode`.
+    pub fn draw_status(&self, uibuf: &mut UIBuffer, x: uint, y: uint, focused: bool, mode: &str) {
+        let buffer_status = self.buffer.borrow().get_status_text();
         let cursor_status = self.cursor.get_status_text();
-        let status_text = format!("{} {}", buffer_status, cursor_status).into_bytes();
-        let status_text_len = status_text.len();
+        let status_text = if focused {
+            format!("-- {} -- {} {}", mode, buffer_status, cursor_status)
+        } else {
+            format!("{} {}", buffer_status, cursor_status)
+        };
+        self.draw_status_line(uibuf, x, y, status_text.as_slice(), focused);
+    }
+
+    /// Draw a message in place of the usual status text
+    pub fn draw_message(&self, uibuf: &mut UIBuffer, x: uint, y: uint, message: &str) {
+        self.draw_status_line(uibuf, x, y, message, true);
+    }
+
+    fn draw_status_line(&self, uibuf: &mut UIBuffer, x: uint, y: uint, text: &str, focused: bool) {
+        let mut status_text = text.chars();
         let width = self.get_width();
         let height = self.get_height();
-
+        let bg = if focused { rustbox::Color::Blue } else { rustbox::Color::White };
 
         for index in range(0, width) {
-            let mut ch: char = ' ';
-            if index < status_text_len {
-                ch = status_text[index] as char;
+            let ch = status_text.next().unwrap_or(' ');
+            uibuf.update_cell(x + index, y + height, ch, rustbox::Color::Black, bg);
+        }
+    }
+
+    /// Get the position of the cursor, relative to the top left of the View
+    pub fn get_cursor_screen_position(&self) -> (uint, uint) {
+        if self.options.wrap {
+            return self.get_wrapped_cursor_position()
+        }
+
+        let column = self.get_cursor_column();
+        let linenum = self.cursor.get_linenum();
+
+        (self.get_gutter_width() + column - self.left_col, linenum - self.top_line_num)
+    }
+
+    /// Get the (offset, line_num) position in the buffer of the text drawn at
+    /// (x, y), relative to the top left of the View.
+    ///
+    /// Points in the gutter give the start of the row, and points past the
+    /// end of a row or of the buffer give the end of it.
+    pub fn get_position_at(&self, x: uint, y: uint) -> (uint, uint) {
+        let gutter_width = self.get_gutter_width();
+        let column = if x > gutter_width { x - gutter_width } else { 0 };
+        let tab_width = self.options.tab_width;
+        let buffer = self.buffer.borrow();
+        let last_line = buffer.line_count() - 1;
+
+        if !self.options.wrap {
+            let line_num = cmp::min(self.top_line_num + y, last_line);
+            let line = buffer.get_line(line_num);
+            let offset = line.offset_at_column(0, line.len(), self.left_col + column, tab_width);
+            return (offset, line_num)
+        }
+
+        // count down the rows of each line until reaching row y
+        let width = self.get_text_width();
+        let mut rows_above = 0;
+        for (index, line) in buffer.lines_from(self.top_line_num).enumerate() {
+            let points = line.wrap_points(width, tab_width);
+            if y < rows_above + points.len() {
+                let row = y - rows_above;
+                let end = if row + 1 < points.len() {
+                    line.prev_grapheme_boundary(points[row + 1])
+                } else {
+                    line.len()
+                };
+                let offset = line.offset_at_column(points[row], end, column, tab_width);
+                return (offset, self.top_line_num + index)
             }
-            self.uibuf.update_cell(index, height, ch, rustbox::Color::Black, rustbox::Color::Blue);
+            rows_above += points.len();
+        }
+        (buffer.get_line(last_line).len(), last_line)
+    }
+
+    /// Get the position of the cursor relative to the top left of the View,
+    /// when long lines are wrapped
+    fn get_wrapped_cursor_position(&self) -> (uint, uint) {
+        let (offset, linenum) = self.cursor.get_position();
+        let width = self.get_text_width();
+        let tab_width = self.options.tab_width;
+        let buffer = self.buffer.borrow();
+
+        // count the rows taken by each line above the cursor
+        let mut row = 0;
+        for line in buffer.lines_from(self.top_line_num).take(linenum - self.top_line_num) {
+            row += line.wrap_points(width, tab_width).len();
         }
 
-        self.uibuf.draw_range(height, height+1);
+        let line = buffer.get_line(linenum);
+        let points = line.wrap_points(width, tab_width);
+        let line_row = row_containing(points.as_slice(), offset);
+        let column = line.width_between(points[line_row], offset, tab_width);
+
+        // the end of a line which fills its last row is past the edge, so the
+        // cursor goes at the start of the row below
+        if column >= width {
+            return (self.get_gutter_width(), row + line_row + 1)
+        }
+        (self.get_gutter_width() + column, row + line_row)
+    }
+
+    /// Get the display column of the cursor within its line
+    fn get_cursor_column(&self) -> uint {
+        let (offset, linenum) = self.cursor.get_position();
+        let buffer = self.buffer.borrow();
+        let line = buffer.get_line(linenum);
+        line.width_to(offset, self.options.tab_width)
     }
 
-    pub fn draw_cursor(&self) {
-        let offset = self.cursor.get_offset();
+    /// Scroll the View so that the cursor is visible
+    fn scroll_to_cursor(&mut self) {
+        if self.options.wrap {
+            self.left_col = 0;
+            self.scroll_to_cursor_row();
+        } else {
+            self.scroll_to_cursor_column();
+        }
+    }
+
+    /// Scroll the View vertically so that the cursor stays at least
+    /// `threshold` rows away from the top and bottom edges, when long lines
+    /// are wrapped.
+    fn scroll_to_cursor_row(&mut self) {
+        let height = self.get_height();
+        let threshold = cmp::min(self.threshold as uint, height / 2);
         let linenum = self.cursor.get_linenum();
 
-        utils::draw_cursor(offset, linenum-self.top_line_num);
+        if linenum < self.top_line_num {
+            self.top_line_num = linenum;
+        }
+
+        loop {
+            let (_, row) = self.get_wrapped_cursor_position();
+            if self.top_line_num == 0 || row >= threshold { break }
+            self.top_line_num -= 1;
+        }
+        loop {
+            let (_, row) = self.get_wrapped_cursor_position();
+            if self.top_line_num >= linenum || row + threshold < height { break }
+            self.top_line_num += 1;
+        }
     }
 
-    pub fn resize(&mut self) {
-        let width = self.uibuf.get_width();
-        self.clear();
-        self.uibuf = UIBuffer::new(width, 15);
+    /// Scroll the View horizontally so that the cursor stays at least
+    /// `horizontal_margin` columns away from the left and right edges.
+    fn scroll_to_cursor_column(&mut self) {
+        let column = self.get_cursor_column();
+        let text_width = self.get_text_width();
+        if text_width == 0 { return }
+
+        let width = text_width - 1;
+        let margin = cmp::min(self.options.horizontal_margin, width / 2);
+
+        // lines which fit in the View, with room for the cursor after
+        // them, are never scrolled
+        let line_width = {
+            let buffer = self.buffer.borrow();
+            let line = buffer.get_line(self.cursor.get_linenum());
+            line.width_to(line.len(), self.options.tab_width)
+        };
+        if line_width < width {
+            self.left_col = 0;
+            return
+        }
+
+        if column < self.left_col + margin {
+            self.left_col = if column > margin { column - margin } else { 0 };
+        } else if column + margin >= self.left_col + width {
+            self.left_col = column + margin + 1 - width;
+        }
     }
 
     pub fn move_cursor(&mut self, direction: Direction) {
@@ -164,26 +795,133 @@ impl<'v> View<'v> {
             Direction::Up    => { self.move_cursor_up(); },
             Direction::Down  => { self.move_cursor_down(); },
             Direction::Right => { self.move_cursor_right(); },
-            Direction::Left  => { self.cursor.move_left(); },
+            Direction::Left  => { self.cursor.move_left(&*self.buffer.borrow()); },
         }
+        self.scroll_to_cursor();
     }
 
-    fn move_cursor_right(&mut self) {
-        let cursor_offset = self.cursor.get_offset();
-        let next_offset = cursor_offset + 1;
-        let width = self.get_width() - 1;
+    /// Move the cursor by a word or paragraph, or to the start or end of the
+    /// line or buffer, scrolling to keep it in view
+    pub fn move_cursor_by(&mut self, motion: Motion) {
+        {
+            let buffer = self.buffer.borrow();
+            let word_chars = self.options.word_chars.as_slice();
+            match motion {
+                Motion::NextWord      => self.cursor.move_to_next_word(&*buffer, word_chars),
+                Motion::PrevWord      => self.cursor.move_to_prev_word(&*buffer, word_chars),
+                Motion::WordEnd       => self.cursor.move_to_word_end(&*buffer, word_chars),
+                Motion::LineStart     => self.cursor.move_to_line_start(),
+                Motion::LineEnd       => self.cursor.move_to_line_end(&*buffer),
+                Motion::FirstNonBlank => self.cursor.move_to_first_non_blank(&*buffer),
+                Motion::NextParagraph => self.cursor.move_to_next_paragraph(&*buffer),
+                Motion::PrevParagraph => self.cursor.move_to_prev_paragraph(&*buffer),
+                Motion::BufferStart   => self.cursor.move_to_buffer_start(),
+                Motion::BufferEnd     => self.cursor.move_to_buffer_end(&*buffer),
+            }
+        }
+        let (offset, line_num) = self.cursor.get_position();
+        self.move_cursor_to(offset, line_num);
+    }
+
+    /// Scroll the View up or down by a screen, keeping a line of the old
+    /// screen in view. At the top or bottom of the buffer the cursor moves to
+    /// the first or last line instead.
+    pub fn scroll_page(&mut self, direction: Direction) {
+        let visible = self.get_visible_line_count();
+        let last_line = self.buffer.borrow().line_count() - 1;
+        let lines = cmp::max(visible, 2) - 1;
 
-        if next_offset < width {
-            self.cursor.move_right()
+        match direction {
+            Direction::Up if self.top_line_num == 0 => {
+                self.set_cursor_line(0);
+                self.scroll_to_cursor();
+            }
+            Direction::Down if self.top_line_num + visible > last_line => {
+                self.set_cursor_line(last_line);
+                self.scroll_to_cursor();
+            }
+            Direction::Up   => { self.scroll_by(-(lines as int)); }
+            Direction::Down => { self.scroll_by(lines as int); }
+            _               => {}
         }
     }
 
+    /// Scroll the View up, for a negative number of `lines`, or down,
+    /// leaving the cursor where it is unless it would go out of view
+    pub fn scroll_by(&mut self, lines: int) {
+        let last_line = self.buffer.borrow().line_count() - 1;
+        let top_line_num = cmp::max(self.top_line_num as int + lines, 0) as uint;
+        self.top_line_num = cmp::min(top_line_num, last_line);
+
+        // keep the cursor on the nearest line still in view
+        let linenum = self.cursor.get_linenum();
+        let first_visible = self.top_line_num;
+        let last_visible = first_visible + self.get_visible_line_count() - 1;
+        if linenum < first_visible {
+            self.set_cursor_line(first_visible);
+        } else if linenum > last_visible {
+            self.set_cursor_line(last_visible);
+        }
+        if !self.options.wrap {
+            self.scroll_to_cursor_column();
+        }
+    }
+
+    /// Scroll the View so that the cursor line is in the middle of it, or as
+    /// near as it can be
+    pub fn recenter(&mut self) {
+        let middle = self.get_height() / 2;
+        self.top_line_num = self.cursor.get_linenum();
+
+        while self.top_line_num > 0 {
+            self.top_line_num -= 1;
+            let (_, row) = self.get_cursor_screen_position();
+            if row > middle {
+                // a wrapped line took the cursor past the middle
+                self.top_line_num += 1;
+                break
+            }
+            if row == middle { break }
+        }
+    }
+
+    /// Get the number of lines shown in full from the top line down, which
+    /// is fewer than the height when long lines are wrapped. At least one
+    /// line is counted, even if it doesn't fit.
+    fn get_visible_line_count(&self) -> uint {
+        let height = self.get_height();
+        let buffer = self.buffer.borrow();
+        let lines_left = buffer.line_count() - self.top_line_num;
+        if !self.options.wrap {
+            return cmp::max(cmp::min(height, lines_left), 1)
+        }
+
+        let width = self.get_text_width();
+        let tab_width = self.options.tab_width;
+        let mut rows = 0;
+        let mut count = 0;
+        for line in buffer.lines_from(self.top_line_num) {
+            rows += line.wrap_points(width, tab_width).len();
+            if rows > height { break }
+            count += 1;
+        }
+        cmp::max(count, 1)
+    }
+
+    fn move_cursor_right(&mut self) {
+        self.cursor.move_right(&*self.buffer.borrow())
+    }
+
     // TODO(greg): refactor this method with move_cursor_down
     pub fn move_cursor_up(&mut self) {
+        if self.options.wrap {
+            return self.move_cursor_by_row(Direction::Up)
+        }
+
         let cursor_linenum = self.cursor.get_linenum();
         let prev_linenum = cursor_linenum - 1;
 
-        let num_lines = self.buffer.lines.len() - 1;
+        let num_lines = self.buffer.borrow().line_count() - 1;
         if prev_linenum > num_lines { return }
 
         self.set_cursor_line(prev_linenum);
@@ -200,10 +938,14 @@ impl<'v> View<'v> {
 
     // TODO(greg): refactor this method with move_cursor_up
     pub fn move_cursor_down(&mut self) {
+        if self.options.wrap {
+            return self.move_cursor_by_row(Direction::Down)
+        }
+
         let cursor_linenum = self.cursor.get_linenum();
         let next_linenum = cursor_linenum + 1;
 
-        let num_lines = self.buffer.lines.len() - 1;
+        let num_lines = self.buffer.borrow().line_count() - 1;
         if next_linenum > num_lines { return }
 
         self.set_cursor_line(next_linenum);
@@ -218,9 +960,54 @@ impl<'v> View<'v> {
         }
     }
 
+    /// Move the cursor up or down by one row on screen, when long lines are
+    /// wrapped. The cursor keeps its column within the row where possible.
+    fn move_cursor_by_row(&mut self, direction: Direction) {
+        let (offset, linenum) = self.cursor.get_position();
+        let width = self.get_text_width();
+        let tab_width = self.options.tab_width;
+        let num_lines = self.buffer.borrow().line_count();
+
+        // find the line and row to move to, None meaning the last row
+        let (column, target_line, target_row) = {
+            let buffer = self.buffer.borrow();
+            let line = buffer.get_line(linenum);
+            let points = line.wrap_points(width, tab_width);
+            let row = row_containing(points.as_slice(), offset);
+            let column = line.width_between(points[row], offset, tab_width);
+
+            match direction {
+                Direction::Up if row > 0                  => (column, linenum, Some(row - 1)),
+                Direction::Up if linenum > 0              => (column, linenum - 1, None),
+                Direction::Down if row + 1 < points.len() => (column, linenum, Some(row + 1)),
+                Direction::Down if linenum + 1 < num_lines => (column, linenum + 1, Some(0)),
+                _                                         => return,
+            }
+        };
+
+        let new_offset = {
+            let buffer = self.buffer.borrow();
+            let line = buffer.get_line(target_line);
+            let points = line.wrap_points(width, tab_width);
+            let row = target_row.unwrap_or(points.len() - 1);
+
+            // stop short of the start of the next row, so the cursor stays
+            // on this one
+            let end = if row + 1 < points.len() {
+                line.prev_grapheme_boundary(points[row + 1])
+            } else {
+                line.len()
+            };
+            line.offset_at_column(points[row], end, column, tab_width)
+        };
+
+        self.set_cursor_line(target_line);
+        self.cursor.set_offset(new_offset);
+        self.scroll_to_cursor_row();
+    }
+
     fn set_cursor_line(&mut self, linenum: uint) {
-        let line = &self.buffer.lines[linenum];
-        self.cursor.set_line(Some(line));
+        self.cursor.set_line(linenum, &*self.buffer.borrow());
     }
 
     fn move_top_line_n_times(&mut self, mut num_times: int) {
@@ -250,72 +1037,219 @@ impl<'v> View<'v> {
         let (offset, line_num) = self.cursor.get_position();
 
         if offset == 0 && direction.is_left() {
-            let offset = self.buffer.join_line_with_previous(offset, line_num);
+            // nothing to join the first line onto
+            if line_num == 0 { return }
+
+            let offset = self.buffer.borrow_mut().join_line_with_previous(offset, line_num);
             self.move_cursor_up();
             self.cursor.set_offset(offset);
+            self.record(Change::Delete(String::from_str("\n")), (offset, line_num - 1), (0, line_num));
             return
         }
 
-        let line_len = self.cursor.get_line_length();
+        let line_len = self.cursor.get_line_length(&*self.buffer.borrow());
         if offset == line_len && direction.is_right() {
-            self.buffer.join_line_with_previous(offset, line_num+1);
+            // nothing to join onto the end of the last line
+            if line_num + 1 == self.buffer.borrow().line_count() { return }
+
+            self.buffer.borrow_mut().join_line_with_previous(offset, line_num+1);
+            self.record(Change::Delete(String::from_str("\n")), (offset, line_num), (offset, line_num));
             return
         }
 
-        match direction {
-            Direction::Left  => self.cursor.delete_backward_char(),
-            Direction::Right => self.cursor.delete_forward_char(),
-            _                => {}
-        }
+        let removed = match direction {
+            Direction::Left  => self.cursor.delete_backward_char(&mut *self.buffer.borrow_mut()),
+            Direction::Right => self.cursor.delete_forward_char(&mut *self.buffer.borrow_mut()),
+            _                => return,
+        };
+        let position = self.cursor.get_position();
+        self.record(Change::Delete(removed), position, (offset, line_num));
+    }
+
+    /// Start a new line below the one the cursor is on, and move the cursor
+    /// onto it
+    pub fn open_line_below(&mut self) {
+        let line_len = self.cursor.get_line_length(&*self.buffer.borrow());
+        self.cursor.set_offset(line_len);
+        self.insert_line();
     }
 
+    /// Insert spaces up to the next tab stop
     pub fn insert_tab(&mut self) {
-        // A tab is just 4 spaces
-        for _ in range(0i, 4) {
+        let column = self.get_cursor_column();
+        let tab_width = self.options.tab_width;
+        for _ in range(0, tab_width - column % tab_width) {
             self.insert_char(' ');
         }
     }
 
     pub fn insert_char(&mut self, ch: char) {
-        self.cursor.insert_char(ch);
+        let position = self.cursor.get_position();
+        self.cursor.insert_char(&mut *self.buffer.borrow_mut(), ch);
+        self.record(Change::Insert(ch.to_string()), position, position);
     }
 
     pub fn insert_line(&mut self) {
         let (offset, line_num) = self.cursor.get_position();
-        self.buffer.insert_line(offset, line_num);
+        self.buffer.borrow_mut().insert_line(offset, line_num);
 
         self.move_cursor_down();
         self.cursor.set_offset(0);
+        self.record(Change::Insert(String::from_str("\n")), (offset, line_num), (offset, line_num));
+    }
+
+    /// Undo the most recent change to the buffer, restoring the cursor
+    /// to where it was before the change was made.
+    pub fn undo(&mut self) {
+        let position = self.buffer.borrow_mut().undo();
+        if let Some((offset, line_num)) = position {
+            self.move_cursor_to(offset, line_num);
+        }
+    }
+
+    /// Redo the most recently undone change to the buffer, restoring the
+    /// cursor to where it was after the change was made.
+    pub fn redo(&mut self) {
+        let position = self.buffer.borrow_mut().redo();
+        if let Some((offset, line_num)) = position {
+            self.move_cursor_to(offset, line_num);
+        }
+    }
+
+    /// Place the cursor at the given position, scrolling the view if the
+    /// position is not currently visible.
+    pub fn move_cursor_to(&mut self, offset: uint, line_num: uint) {
+        self.set_cursor_line(line_num);
+        self.cursor.set_offset(offset);
+
+        let height = self.get_height();
+        if line_num < self.top_line_num {
+            self.top_line_num = line_num;
+        } else if line_num >= self.top_line_num + height {
+            self.top_line_num = line_num - height + 1;
+        }
+        self.scroll_to_cursor();
+    }
+
+    /// Add an operation to the buffer's undo history. The cursor is expected
+    /// to already be in the position the change left it.
+    fn record(&mut self, change: Change, position: (uint, uint), cursor_before: (uint, uint)) {
+        let op = Operation {
+            change: change,
+            position: position,
+            cursor_before: cursor_before,
+            cursor_after: self.cursor.get_position(),
+        };
+        self.buffer.borrow_mut().record(op);
+
+        // the selection would no longer line up with the text
+        self.mark = None;
+
+        // the change may have moved the cursor out of view
+        self.scroll_to_cursor();
+    }
+}
+
+/// Parse a position entered as `line` or `line:column`, counting from 1, into
+/// an (offset, line_num) position in `buffer`. A line or column past the end
+/// of the buffer or the line is taken to mean the end.
+pub fn parse_position(text: &str, buffer: &Buffer) -> Option<(uint, uint)> {
+    let numbers: Vec<Option<uint>> = text.split(':').map(|n| from_str(n.trim())).collect();
+    let (line, column) = match numbers.as_slice() {
+        [Some(line)]               => (line, 1),
+        [Some(line), Some(column)] => (line, column),
+        _                          => return None,
+    };
+    if line == 0 || column == 0 { return None }
+
+    let line_num = cmp::min(line, buffer.line_count()) - 1;
+    let line = buffer.get_line(line_num);
+    let offset = line.data.as_slice().grapheme_indices(true).nth(column - 1)
+                     .map_or(line.len(), |(offset, _)| offset);
+    Some((offset, line_num))
+}
+
+/// Check whether the (offset, line_num) position `a` comes before `b`
+fn before(a: (uint, uint), b: (uint, uint)) -> bool {
+    let (a_offset, a_line) = a;
+    let (b_offset, b_line) = b;
+    (a_line, a_offset) < (b_line, b_offset)
+}
+
+/// Check whether `position` falls within `region`
+fn in_region(region: Option<Region>, position: (uint, uint)) -> bool {
+    match region {
+        Some((start, end)) => !before(position, start) && before(position, end),
+        None               => false,
+    }
+}
+
+/// Draw `ch` in a single cell, in the colour for its syntax `style`, or in
+/// reverse colours if it is highlighted
+fn draw_cell(uibuf: &mut UIBuffer, x: uint, y: uint, ch: char, highlight: bool, style: Style) {
+    if highlight {
+        uibuf.update_cell(x, y, ch, rustbox::Color::Black, rustbox::Color::White);
+    } else {
+        uibuf.update_cell(x, y, ch, style.color(), rustbox::Color::Default);
+    }
+}
+
+/// Add a tab stop's worth of spaces to the start of `text`, unless it is
+/// empty, or take off a tab or up to a tab stop's worth of spaces
+fn change_line_indent(text: &str, tab_width: uint, indent: bool) -> String {
+    if indent {
+        if text.is_empty() { return String::new() }
+        let mut indented = String::from_char(tab_width, ' ');
+        indented.push_str(text);
+        indented
+    } else if text.starts_with("\t") {
+        String::from_str(text.slice_from(1))
+    } else {
+        let spaces = text.chars().take(tab_width).take_while(|&ch| ch == ' ').count();
+        String::from_str(text.slice_from(spaces))
     }
 }
 
+/// Get the index of the row containing `offset`, given the offsets at which
+/// each row of a wrapped line starts
+fn row_containing(points: &[uint], offset: uint) -> uint {
+    let mut row = 0;
+    for (index, &start) in points.iter().enumerate() {
+        if start > offset { break }
+        row = index;
+    }
+    row
+}
+
 #[cfg(test)]
 mod tests {
 
     use std::cell::RefCell;
+    use std::rc::Rc;
 
     use buffer::{Line, Buffer};
-    use cursor::{Cursor, Direction};
-    use view::View;
+    use cursor::{Direction, Motion};
+    use options::Options;
+    use rope::Rope;
     use uibuf::UIBuffer;
+    use view::{LineNumbers, View, parse_position};
     use utils::data_from_str;
 
-    fn setup_view<'v>() -> View<'v> {
-        let mut view = View {
-            buffer: Buffer::new(),
-            top_line_num: 0,
-            cursor: Cursor::new(),
-            uibuf: UIBuffer::new(50, 50),
-            threshold: 5,
-        };
+    fn setup_view() -> View {
+        let mut buffer = Buffer::new();
+
+        let first_line = Line::new(data_from_str("test"));
+        let second_line = Line::new(data_from_str("second"));
 
-        let first_line = RefCell::new(Line::new(data_from_str("test"), 0));
-        let second_line = RefCell::new(Line::new(data_from_str("second"), 1));
+        buffer.lines = Rope::from_vec(vec!(first_line, second_line));
 
-        view.buffer.lines = vec!(first_line, second_line);
-        view.cursor.set_line(Some(&view.buffer.lines[0]));
+        return View::new(Rc::new(RefCell::new(buffer)), Options::new(), 50, 50)
+    }
 
-        return view
+    fn cursor_line_data(view: &View) -> String {
+        let buffer = view.buffer.borrow();
+        let line = buffer.get_line(view.cursor.get_linenum());
+        line.data.clone()
     }
 
     #[test]
@@ -324,7 +1258,14 @@ mod tests {
         view.move_cursor_down();
 
         assert_eq!(view.cursor.get_linenum(), 1);
-        assert_eq!(view.cursor.get_line().borrow().data, data_from_str("second"));
+        assert_eq!(cursor_line_data(&view), data_from_str("second"));
+    }
+
+    #[test]
+    fn view_with_no_rows_has_no_height() {
+        let mut view = setup_view();
+        view.resize(50, 0);
+        assert_eq!(view.get_height(), 0);
     }
 
     #[test]
@@ -333,18 +1274,18 @@ mod tests {
         view.move_cursor_down();
         view.move_cursor_up();
         assert_eq!(view.cursor.get_linenum(), 0);
-        assert_eq!(view.cursor.get_line().borrow().data, data_from_str("test"));
+        assert_eq!(cursor_line_data(&view), data_from_str("test"));
     }
 
     #[test]
     fn test_insert_line() {
         let mut view = setup_view();
-        view.cursor.move_right();
+        view.cursor.move_right(&*view.buffer.borrow());
         view.insert_line();
 
-        assert_eq!(view.buffer.lines.len(), 3);
+        assert_eq!(view.buffer.borrow().line_count(), 3);
         assert_eq!(view.cursor.get_offset(), 0);
-        assert_eq!(view.cursor.get_line().borrow().linenum, 1);
+        assert_eq!(view.cursor.get_linenum(), 1);
     }
 
     #[test]
@@ -352,7 +1293,7 @@ mod tests {
         let mut view = setup_view();
         view.insert_char('t');
 
-        assert_eq!(view.cursor.get_line().borrow().data, data_from_str("ttest"));
+        assert_eq!(cursor_line_data(&view), data_from_str("ttest"));
     }
 
     #[test]
@@ -360,16 +1301,16 @@ mod tests {
         let mut view = setup_view();
         view.delete_char(Direction::Right);
 
-        assert_eq!(view.cursor.get_line().borrow().data, data_from_str("est"));
+        assert_eq!(cursor_line_data(&view), data_from_str("est"));
     }
 
     #[test]
     fn test_delete_char_to_left() {
         let mut view = setup_view();
-        view.cursor.move_right();
+        view.cursor.move_right(&*view.buffer.borrow());
         view.delete_char(Direction::Left);
 
-        assert_eq!(view.cursor.get_line().borrow().data, data_from_str("est"));
+        assert_eq!(cursor_line_data(&view), data_from_str("est"));
     }
 
     #[test]
@@ -378,7 +1319,7 @@ mod tests {
         view.move_cursor_down();
         view.delete_char(Direction::Left);
 
-        assert_eq!(view.cursor.get_line().borrow().data, data_from_str("testsecond"));
+        assert_eq!(cursor_line_data(&view), data_from_str("testsecond"));
     }
 
     #[test]
@@ -387,7 +1328,7 @@ mod tests {
         view.cursor.set_offset(4);
         view.delete_char(Direction::Right);
 
-        assert_eq!(view.cursor.get_line().borrow().data, data_from_str("testsecond"));
+        assert_eq!(cursor_line_data(&view), data_from_str("testsecond"));
     }
 
     #[test]
@@ -395,7 +1336,569 @@ mod tests {
         let mut view = setup_view();
         view.delete_char(Direction::Left);
 
-        assert_eq!(view.buffer.lines.len(), 2);
-        assert_eq!(view.cursor.get_line().borrow().data, data_from_str("test"));
+        assert_eq!(view.buffer.borrow().line_count(), 2);
+        assert_eq!(cursor_line_data(&view), data_from_str("test"));
+    }
+
+    #[test]
+    fn deleting_a_multibyte_char_can_be_undone() {
+        let mut view = setup_view();
+        view.insert_char('漢');
+        view.delete_char(Direction::Left);
+        assert_eq!(cursor_line_data(&view), data_from_str("test"));
+
+        view.undo();
+        assert_eq!(cursor_line_data(&view), data_from_str("漢test"));
+        assert_eq!(view.cursor.get_position(), (3, 0));
+    }
+
+    #[test]
+    fn cursor_screen_position_accounts_for_wide_chars() {
+        let mut view = setup_view();
+        view.insert_char('漢');
+        view.insert_char('a');
+
+        assert_eq!(view.cursor.get_offset(), 4);
+        assert_eq!(view.get_cursor_screen_position(), (3, 0));
+    }
+
+    #[test]
+    fn cursor_screen_position_accounts_for_tabs() {
+        let mut view = setup_view();
+        view.insert_char('\t');
+        view.insert_char('a');
+
+        assert_eq!(view.cursor.get_offset(), 2);
+        assert_eq!(view.get_cursor_screen_position(), (5, 0));
+    }
+
+    #[test]
+    fn insert_tab_inserts_spaces_to_the_next_tab_stop() {
+        let mut view = setup_view();
+        view.insert_char('a');
+        view.insert_tab();
+
+        assert_eq!(cursor_line_data(&view), data_from_str("a   test"));
+        assert_eq!(view.cursor.get_offset(), 4);
+    }
+
+    #[test]
+    fn view_scrolls_right_to_follow_the_cursor() {
+        let mut view = setup_view();
+        for _ in range(0u, 60) {
+            view.insert_char('x');
+        }
+
+        // 49 text columns, with 5 columns of margin to the right of the cursor
+        assert_eq!(view.left_col, 17);
+        assert_eq!(view.get_cursor_screen_position(), (43, 0));
+    }
+
+    #[test]
+    fn view_scrolls_left_to_follow_the_cursor() {
+        let mut view = setup_view();
+        for _ in range(0u, 60) {
+            view.insert_char('x');
+        }
+        for _ in range(0u, 40) {
+            view.move_cursor(Direction::Left);
+        }
+
+        assert_eq!(view.left_col, 15);
+        assert_eq!(view.get_cursor_screen_position(), (5, 0));
+    }
+
+    #[test]
+    fn moving_to_a_shorter_line_scrolls_back() {
+        let mut view = setup_view();
+        for _ in range(0u, 60) {
+            view.insert_char('x');
+        }
+        view.move_cursor(Direction::Down);
+
+        assert_eq!(view.left_col, 0);
+        assert_eq!(view.get_cursor_screen_position(), (6, 1));
+    }
+
+    fn setup_wrapped_view() -> View {
+        let mut view = setup_view();
+        view.resize(11, 50);
+        view.toggle_wrap();
+        for ch in "aaaa bbbb cccc dd".chars() {
+            view.insert_char(ch);
+        }
+        view
+    }
+
+    #[test]
+    fn wrapped_cursor_position_includes_the_gutter() {
+        let view = setup_wrapped_view();
+
+        // rows of at most 10 columns: "aaaa bbbb ", "cccc ", "ddtest"
+        assert_eq!(view.cursor.get_offset(), 17);
+        assert_eq!(view.get_cursor_screen_position(), (3, 2));
+    }
+
+    #[test]
+    fn cursor_after_a_full_wrapped_row_starts_the_next_row() {
+        let mut view = setup_view();
+        view.resize(10, 50);
+        view.toggle_wrap();
+        for ch in "abcdef".chars() {
+            view.insert_char(ch);
+        }
+        for _ in range(0u, 4) {
+            view.move_cursor(Direction::Right);
+        }
+
+        // "abcdeftest" fills the 10 columns of the view exactly
+        assert_eq!(view.cursor.get_offset(), 10);
+        assert_eq!(view.get_cursor_screen_position(), (0, 1));
+    }
+
+    #[test]
+    fn screen_positions_map_back_to_wrapped_rows() {
+        let view = setup_wrapped_view();
+
+        assert_eq!(view.get_position_at(3, 1), (12, 0));
+        assert_eq!(view.get_position_at(9, 1), (14, 0));
+        assert_eq!(view.get_position_at(1, 3), (0, 1));
+        assert_eq!(view.get_position_at(5, 10), (6, 1));
+    }
+
+    #[test]
+    fn line_numbers_count_from_the_cursor_line() {
+        assert_eq!(LineNumbers::Absolute.number(4, 2), 5);
+        assert_eq!(LineNumbers::Relative.number(4, 2), 2);
+        assert_eq!(LineNumbers::Relative.number(0, 2), 2);
+        assert_eq!(LineNumbers::Relative.number(2, 2), 0);
+        assert_eq!(LineNumbers::Hybrid.number(0, 2), 2);
+        assert_eq!(LineNumbers::Hybrid.number(2, 2), 3);
+    }
+
+    #[test]
+    fn gutter_widens_with_the_line_count() {
+        let mut view = setup_view();
+        assert_eq!(view.get_gutter_width(), 0);
+
+        view.set_line_numbers(LineNumbers::Absolute);
+        assert_eq!(view.get_gutter_width(), 4);
+        assert_eq!(view.get_cursor_screen_position(), (4, 0));
+
+        for _ in range(0u, 1000) {
+            view.insert_line();
+        }
+        assert_eq!(view.get_gutter_width(), 5);
+    }
+
+    #[test]
+    fn views_narrower_than_the_gutter_draw_only_what_fits() {
+        let mut view = setup_view();
+        view.set_line_numbers(LineNumbers::Absolute);
+        view.resize(2, 3);
+        for _ in range(0u, 4) {
+            view.move_cursor(Direction::Right);
+        }
+        assert_eq!(view.left_col, 0);
+
+        // anything drawn outside the View would be outside the UIBuffer
+        let mut uibuf = UIBuffer::new(2, 3);
+        view.draw(&mut uibuf, 0, 0);
+        view.toggle_wrap();
+        view.draw(&mut uibuf, 0, 0);
+    }
+
+    #[test]
+    fn wrapped_lines_leave_room_for_line_numbers() {
+        let mut view = setup_wrapped_view();
+        view.set_line_numbers(LineNumbers::Hybrid);
+
+        // 7 columns for text: "aaaa ", "bbbb ", "cccc ", "ddtest"
+        assert_eq!(view.get_cursor_screen_position(), (6, 3));
+    }
+
+    #[test]
+    fn moving_up_and_down_goes_by_row_when_wrapping() {
+        let mut view = setup_wrapped_view();
+
+        view.move_cursor(Direction::Up);
+        assert_eq!(view.cursor.get_position(), (12, 0));
+
+        view.move_cursor(Direction::Down);
+        assert_eq!(view.cursor.get_position(), (17, 0));
+
+        view.move_cursor(Direction::Down);
+        assert_eq!(view.cursor.get_position(), (2, 1));
+
+        view.move_cursor(Direction::Up);
+        assert_eq!(view.cursor.get_position(), (17, 0));
+    }
+
+    fn setup_long_view() -> View {
+        let mut buffer = Buffer::new();
+        buffer.lines = Rope::from_vec(range(0u, 100).map(|n| Line::new(n.to_string())).collect());
+        View::new(Rc::new(RefCell::new(buffer)), Options::new(), 50, 11)
+    }
+
+    #[test]
+    fn jumping_to_the_end_of_the_buffer_scrolls_to_it() {
+        let mut view = setup_long_view();
+
+        view.move_cursor_by(Motion::BufferEnd);
+        assert_eq!(view.cursor.get_position(), (2, 99));
+        assert!(view.top_line_num <= 99 && 99 < view.top_line_num + view.get_height());
+
+        view.move_cursor_by(Motion::BufferStart);
+        assert_eq!(view.top_line_num, 0);
+    }
+
+    #[test]
+    fn scrolling_keeps_the_cursor_in_view() {
+        let mut view = setup_long_view();
+        view.move_cursor_to(0, 5);
+
+        view.scroll_by(3);
+        assert_eq!(view.top_line_num, 3);
+        assert_eq!(view.cursor.get_linenum(), 5);
+
+        view.scroll_by(10);
+        assert_eq!(view.top_line_num, 13);
+        assert_eq!(view.cursor.get_linenum(), 13);
+
+        view.scroll_by(-20);
+        assert_eq!(view.top_line_num, 0);
+        assert_eq!(view.cursor.get_linenum(), 9);
     }
+
+    #[test]
+    fn screen_positions_skip_the_gutter() {
+        let mut view = setup_long_view();
+        view.set_line_numbers(LineNumbers::Absolute);
+        view.top_line_num = 20;
+
+        assert_eq!(view.get_position_at(5, 5), (1, 25));
+        assert_eq!(view.get_position_at(0, 5), (0, 25));
+        assert_eq!(view.get_position_at(30, 5), (2, 25));
+
+        view.top_line_num = 95;
+        assert_eq!(view.get_position_at(30, 8), (2, 99));
+    }
+
+    #[test]
+    fn paging_keeps_a_line_of_the_old_screen() {
+        let mut view = setup_long_view();
+
+        view.scroll_page(Direction::Down);
+        assert_eq!(view.top_line_num, 9);
+        assert_eq!(view.cursor.get_linenum(), 9);
+
+        view.scroll_page(Direction::Up);
+        assert_eq!(view.top_line_num, 0);
+        assert_eq!(view.cursor.get_linenum(), 9);
+
+        view.scroll_page(Direction::Up);
+        assert_eq!(view.cursor.get_linenum(), 0);
+    }
+
+    #[test]
+    fn paging_down_at_the_end_moves_to_the_last_line() {
+        let mut view = setup_long_view();
+        view.move_cursor_to(0, 95);
+
+        view.scroll_page(Direction::Down);
+        assert_eq!(view.top_line_num, 95);
+        assert_eq!(view.cursor.get_linenum(), 95);
+
+        view.scroll_page(Direction::Down);
+        assert_eq!(view.cursor.get_linenum(), 99);
+    }
+
+    #[test]
+    fn recentering_puts_the_cursor_line_in_the_middle() {
+        let mut view = setup_long_view();
+        view.move_cursor_to(0, 50);
+
+        view.recenter();
+        assert_eq!(view.top_line_num, 45);
+
+        view.move_cursor_to(0, 2);
+        view.recenter();
+        assert_eq!(view.top_line_num, 0);
+    }
+
+    #[test]
+    fn positions_are_parsed_from_one() {
+        let mut buffer = Buffer::new();
+        buffer.lines = Rope::from_vec(vec!(
+            Line::new(data_from_str("first")),
+            Line::new(data_from_str("漢字x")),
+        ));
+
+        assert_eq!(parse_position("2", &buffer), Some((0, 1)));
+        assert_eq!(parse_position(" 2:3 ", &buffer), Some((6, 1)));
+        assert_eq!(parse_position("2:10", &buffer), Some((7, 1)));
+        assert_eq!(parse_position("50", &buffer), Some((0, 1)));
+        assert_eq!(parse_position("0", &buffer), None);
+        assert_eq!(parse_position("one", &buffer), None);
+        assert_eq!(parse_position("1:2:3", &buffer), None);
+    }
+
+    #[test]
+    fn word_motions_use_the_word_chars_option() {
+        let mut options = Options::new();
+        options.word_chars = String::from_str("-");
+        let mut buffer = Buffer::new();
+        buffer.lines = Rope::from_vec(vec!(Line::new(data_from_str("a-b_c"))));
+        let mut view = View::new(Rc::new(RefCell::new(buffer)), options, 50, 10);
+
+        view.move_cursor_by(Motion::NextWord);
+        assert_eq!(view.cursor.get_offset(), 3);
+        view.move_cursor_by(Motion::WordEnd);
+        assert_eq!(view.cursor.get_offset(), 4);
+    }
+
+    #[test]
+    fn undo_restores_text_and_cursor_position() {
+        let mut view = setup_view();
+        view.cursor.set_offset(2);
+        view.insert_char('x');
+        view.undo();
+
+        assert_eq!(cursor_line_data(&view), data_from_str("test"));
+        assert_eq!(view.cursor.get_position(), (2, 0));
+    }
+
+    #[test]
+    fn undo_of_insert_line_rejoins_the_lines() {
+        let mut view = setup_view();
+        view.cursor.set_offset(2);
+        view.insert_line();
+        view.undo();
+
+        assert_eq!(view.buffer.borrow().line_count(), 2);
+        assert_eq!(cursor_line_data(&view), data_from_str("test"));
+        assert_eq!(view.cursor.get_position(), (2, 0));
+    }
+
+    #[test]
+    fn views_sharing_a_buffer_keep_their_own_cursors() {
+        let mut view = setup_view();
+        let mut other = view.clone();
+        other.move_cursor_down();
+        view.insert_char('x');
+
+        assert_eq!(view.cursor.get_position(), (1, 0));
+        assert_eq!(other.cursor.get_position(), (0, 1));
+        assert_eq!(cursor_line_data(&other), data_from_str("second"));
+        assert_eq!(cursor_line_data(&view), data_from_str("xtest"));
+    }
+
+    #[test]
+    fn clamp_cursor_keeps_cursor_inside_a_shrunk_buffer() {
+        let mut view = setup_view();
+        let mut other = view.clone();
+        other.move_cursor_down();
+        view.cursor.set_offset(4);
+        view.delete_char(Direction::Right);
+        other.clamp_cursor();
+
+        assert_eq!(other.cursor.get_linenum(), 0);
+    }
+
+    #[test]
+    fn redo_reapplies_an_undone_change() {
+        let mut view = setup_view();
+        view.move_cursor_down();
+        view.delete_char(Direction::Left);
+        view.undo();
+        view.redo();
+
+        assert_eq!(view.buffer.borrow().line_count(), 1);
+        assert_eq!(cursor_line_data(&view), data_from_str("testsecond"));
+        assert_eq!(view.cursor.get_position(), (4, 0));
+    }
+
+    #[test]
+    fn selection_includes_the_grapheme_at_the_far_end() {
+        let mut view = setup_view();
+        view.set_mark();
+        view.move_cursor(Direction::Right);
+
+        assert_eq!(view.get_selection(), Some(((0, 0), (2, 0))));
+    }
+
+    #[test]
+    fn nothing_is_selected_until_the_cursor_leaves_the_mark() {
+        let mut view = setup_view();
+        view.set_mark();
+        assert_eq!(view.get_selection(), None);
+
+        view.move_cursor(Direction::Right);
+        view.move_cursor(Direction::Left);
+        assert_eq!(view.get_selection(), None);
+
+        view.set_visual_mark();
+        assert_eq!(view.get_selection(), Some(((0, 0), (1, 0))));
+    }
+
+    #[test]
+    fn selection_ending_at_the_end_of_a_line_includes_the_line_break() {
+        let mut view = setup_view();
+        view.move_cursor_to(4, 0);
+        view.set_mark();
+        view.move_cursor(Direction::Left);
+
+        assert_eq!(view.get_selection(), Some(((3, 0), (0, 1))));
+    }
+
+    #[test]
+    fn delete_selection_across_lines_can_be_undone() {
+        let mut view = setup_view();
+        view.move_cursor(Direction::Right);
+        view.set_mark();
+        view.move_cursor(Direction::Down);
+        view.delete_selection();
+
+        assert_eq!(cursor_line_data(&view), data_from_str("tcond"));
+        assert_eq!(view.cursor.get_position(), (1, 0));
+        assert_eq!(view.get_selection(), None);
+
+        view.undo();
+        assert_eq!(view.buffer.borrow().line_count(), 2);
+        assert_eq!(cursor_line_data(&view), data_from_str("second"));
+        assert_eq!(view.cursor.get_position(), (1, 1));
+    }
+
+    #[test]
+    fn indenting_changes_every_selected_line_at_once() {
+        let mut view = setup_view();
+        view.move_cursor_to(2, 0);
+        view.set_mark();
+        view.move_cursor(Direction::Down);
+        view.indent_lines();
+
+        assert_eq!(cursor_line_data(&view), data_from_str("    second"));
+        assert_eq!(view.cursor.get_position(), (6, 1));
+        assert_eq!(view.get_selection(), None);
+
+        view.indent_lines();
+        view.dedent_lines();
+        view.dedent_lines();
+        view.dedent_lines();
+        assert_eq!(cursor_line_data(&view), data_from_str("second"));
+        assert_eq!(view.cursor.get_position(), (2, 1));
+
+        view.undo();
+        view.undo();
+        assert_eq!(cursor_line_data(&view), data_from_str("    second"));
+        view.undo();
+        view.undo();
+        assert_eq!(cursor_line_data(&view), data_from_str("second"));
+        assert_eq!(view.buffer.borrow().get_line(0).data, data_from_str("test"));
+    }
+
+    #[test]
+    fn pasting_lines_splits_them_up() {
+        let mut view = setup_view();
+        view.move_cursor_to(2, 1);
+        let region = view.paste("one\ntwo", false);
+
+        assert_eq!(region, ((2, 1), (3, 2)));
+        assert_eq!(view.buffer.borrow().line_count(), 3);
+        assert_eq!(view.buffer.borrow().get_line(1).data, data_from_str("seone"));
+        assert_eq!(cursor_line_data(&view), data_from_str("twocond"));
+        assert_eq!(view.cursor.get_position(), (3, 2));
+
+        view.undo();
+        assert_eq!(view.buffer.borrow().line_count(), 2);
+        assert_eq!(cursor_line_data(&view), data_from_str("second"));
+    }
+
+    #[test]
+    fn text_ending_in_a_line_break_is_pasted_at_the_cursor() {
+        let mut view = setup_view();
+        view.move_cursor_to(2, 1);
+        let region = view.paste("one\n", false);
+
+        assert_eq!(region, ((2, 1), (0, 2)));
+        assert_eq!(view.buffer.borrow().get_line(1).data, data_from_str("seone"));
+        assert_eq!(cursor_line_data(&view), data_from_str("cond"));
+    }
+
+    #[test]
+    fn whole_lines_are_pasted_above_the_cursor_line() {
+        let mut view = setup_view();
+        view.move_cursor_to(2, 1);
+        let line = view.get_cursor_line_text();
+        let region = view.paste(line.as_slice(), true);
+
+        assert_eq!(region, ((0, 1), (0, 2)));
+        assert_eq!(view.buffer.borrow().get_line(1).data, data_from_str("second"));
+        assert_eq!(view.cursor.get_position(), (0, 2));
+
+        let region = view.replace_region(region, "x");
+        assert_eq!(region, ((0, 1), (1, 1)));
+        assert_eq!(cursor_line_data(&view), data_from_str("xsecond"));
+    }
+
+    #[test]
+    fn replacing_a_paste_with_whole_lines_puts_them_above_the_line() {
+        let mut view = setup_view();
+        view.move_cursor_to(2, 1);
+        let region = view.paste("xy", false);
+        let region = view.replace_paste(region, "one\n", true);
+
+        assert_eq!(region, ((0, 1), (0, 2)));
+        assert_eq!(view.buffer.borrow().get_line(1).data, data_from_str("one"));
+        assert_eq!(cursor_line_data(&view), data_from_str("second"));
+        assert_eq!(view.cursor.get_position(), (0, 2));
+
+        view.undo();
+        assert_eq!(cursor_line_data(&view), data_from_str("sexycond"));
+    }
+
+    #[test]
+    fn replacing_pasted_lines_with_other_text_puts_it_at_the_line_start() {
+        let mut view = setup_view();
+        view.move_cursor_to(2, 1);
+        let region = view.paste("one\n", true);
+        let region = view.replace_paste(region, "xy", false);
+
+        assert_eq!(region, ((0, 1), (2, 1)));
+        assert_eq!(view.buffer.borrow().line_count(), 2);
+        assert_eq!(cursor_line_data(&view), data_from_str("xysecond"));
+        assert_eq!(view.cursor.get_position(), (2, 1));
+    }
+
+    #[test]
+    fn delete_line_removes_line_and_break() {
+        let mut view = setup_view();
+        view.move_cursor(Direction::Right);
+        view.delete_line();
+
+        assert_eq!(view.buffer.borrow().line_count(), 1);
+        assert_eq!(cursor_line_data(&view), data_from_str("second"));
+        assert_eq!(view.cursor.get_position(), (0, 0));
+
+        view.undo();
+        assert_eq!(view.buffer.borrow().line_count(), 2);
+        assert_eq!(cursor_line_data(&view), data_from_str("test"));
+        assert_eq!(view.cursor.get_position(), (1, 0));
+    }
+
+    #[test]
+    fn delete_last_line_takes_preceding_break() {
+        let mut view = setup_view();
+        view.move_cursor(Direction::Down);
+        view.delete_line();
+
+        assert_eq!(view.buffer.borrow().line_count(), 1);
+        assert_eq!(cursor_line_data(&view), data_from_str("test"));
+        assert_eq!(view.cursor.get_position(), (0, 0));
+
+        view.delete_line();
+        assert_eq!(view.buffer.borrow().line_count(), 1);
+        assert_eq!(cursor_line_data(&view), data_from_str(""));
+    }
+
 }
@@ -3,21 +3,55 @@ extern crate rustbox;
 extern crate docopt;
 extern crate iota;
 
+#[cfg(not(test))] use std::cmp;
 #[cfg(not(test))] use std::io::stdio;
+#[cfg(not(test))] use std::os;
 #[cfg(not(test))] use docopt::Docopt;
-#[cfg(not(test))] use iota::{Editor, Input};
+#[cfg(not(test))] use iota::{CaseMatching, Editor, Input, LineNumbers, Options, SystemClipboard};
 #[cfg(not(test))] static USAGE: &'static str = "
-Usage: iota [<filename>]
+Usage: iota [options] [<filename>...]
        iota --help
 
 Options:
-    -h, --help     Show this message.
+    -t <n>, --tab-width <n>  Number of columns between tab stops [default: 4].
+    -m <n>, --margin <n>     Number of columns to keep either side of the cursor
+                             when scrolling sideways [default: 5].
+    -w, --wrap               Wrap long lines instead of scrolling sideways.
+    -n <style>, --line-numbers <style>
+                             Number the lines: off, absolute, relative or
+                             hybrid [default: off].
+    --word-chars <chars>     Chars besides letters and digits which count as
+                             part of a word when moving by words [default: _].
+    -i, --ignore-case        Match either case when searching.
+    -I, --case-sensitive     Only match the same case when searching. By
+                             default case is ignored unless the search has an
+                             upper case letter in it.
+    --no-mouse               Leave the mouse to the terminal, for selecting
+                             text with it.
+    --osc52                  Put text cut or copied on the terminal's
+                             clipboard, with the OSC 52 escape sequence.
+    --copy-command <cmd>     Pipe text cut or copied into a shell command,
+                             such as 'xclip -selection clipboard'.
+    --paste-command <cmd>    Paste the output of a shell command, such as
+                             'xclip -o -selection clipboard'.
+    -h, --help               Show this message.
 ";
 
 
 #[deriving(Decodable, Show)]
 struct Args {
-    arg_filename: Option<String>,
+    arg_filename: Vec<String>,
+    flag_tab_width: uint,
+    flag_margin: uint,
+    flag_wrap: bool,
+    flag_line_numbers: String,
+    flag_word_chars: String,
+    flag_ignore_case: bool,
+    flag_case_sensitive: bool,
+    flag_no_mouse: bool,
+    flag_osc52: bool,
+    flag_copy_command: Option<String>,
+    flag_paste_command: Option<String>,
     flag_help: bool,
 }
 
@@ -26,14 +60,44 @@ fn main() {
     let args: Args = Docopt::new(USAGE)
                             .and_then(|d| d.decode())
                             .unwrap_or_else(|e| e.exit());
+    let mut filenames = args.arg_filename.into_iter();
     let source = if stdio::stdin_raw().isatty() {
-        Input::Filename(args.arg_filename)
+        Input::Filename(filenames.next())
     } else {
         Input::Stdin(stdio::stdin())
     };
 
+    let mut options = Options::new();
+    options.tab_width = cmp::max(args.flag_tab_width, 1);
+    options.horizontal_margin = args.flag_margin;
+    options.wrap = args.flag_wrap;
+    options.line_numbers = match LineNumbers::from_name(args.flag_line_numbers.as_slice()) {
+        Some(line_numbers) => line_numbers,
+        None => {
+            let _ = writeln!(&mut stdio::stderr(), "Unknown line number style: {}",
+                             args.flag_line_numbers);
+            os::set_exit_status(1);
+            return
+        }
+    };
+    options.word_chars = args.flag_word_chars;
+    options.mouse = !args.flag_no_mouse;
+    if args.flag_ignore_case {
+        options.case_matching = CaseMatching::Insensitive;
+    } else if args.flag_case_sensitive {
+        options.case_matching = CaseMatching::Sensitive;
+    }
+
+    let mut clipboard = SystemClipboard::new();
+    clipboard.osc52 = args.flag_osc52;
+    clipboard.copy_command = args.flag_copy_command;
+    clipboard.paste_command = args.flag_paste_command;
+
     rustbox::init();
-    let mut editor = Editor::new(source);
+    let mut editor = Editor::new(source, options, clipboard);
+    for filename in filenames {
+        editor.add_buffer(Input::Filename(Some(filename)));
+    }
     editor.start();
     rustbox::shutdown();
 }